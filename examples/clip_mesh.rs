@@ -1,6 +1,7 @@
 use bevy::{prelude::*, pbr::wireframe::{Wireframe, WireframePlugin},};
 use bevy_fluid_engine::hull::*;
 use bevy_fluid_engine::geometry::*;
+use bevy_fluid_engine::physics::liquids::{Liquid, Surface, surface_mesh::{LiquidMesh, SurfaceMeshPlugin}};
 use bevy_debug_camera::{DebugCamera, DebugCameraPlugin};
 
 #[derive(Component)]
@@ -12,6 +13,8 @@ fn main(){
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
     app.add_plugins(WireframePlugin);
+    app.add_plugins(SurfaceMeshPlugin);
+    app.add_plugins(debug::HullDebugPlugin);
     app.add_plugins(DebugCameraPlugin::default());
     app.add_systems(Startup, startup_system);
     app.add_systems(Update, move_plane_system);
@@ -45,7 +48,9 @@ fn startup_system(
     // mesh.set_indices(Some(Indices::U32(vec![
     //     0,1,2
     // ])));
-   let hull_mesh_handle = meshes.add(mesh.clone());
+    //the target's mesh is replaced by the clipped one every frame, so the hull is built once up front
+    let hull = Hull::from_mesh(&mesh, hull_shape::into::DEFAULT_WELD_TOLERANCE).unwrap();
+    let hull_mesh_handle = meshes.add(mesh);
 
 
     
@@ -55,15 +60,16 @@ fn startup_system(
         material: materials.add(Color::BLUE.into()),
         transform: Transform::default(),
         ..default()
-    }).insert(Target).insert(Hull::try_from(mesh).unwrap()).insert(Wireframe);
+    }).insert(Target).insert(hull).insert(Wireframe).insert(debug::HullGizmo::default());
 
-    // plane
+    // liquid, its surface mesh is filled in by the SurfaceMeshPlugin
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(shape::Plane::from_size(5.0))),
         material: materials.add(Color::rgba(0.3, 0.5, 0.3,0.2).into()),
         transform: Transform::default().looking_at(Vec3::Y, Vec3::Z),
         ..default()
-    }).insert(MovingPlane);
+    }).insert(Liquid{surface: Surface::Plane(Plane::default()), ..default()})
+    .insert(LiquidMesh::new(Vec2::splat(5.0), UVec2::splat(8)))
+    .insert(MovingPlane);
 
     // light
     commands.spawn(PointLightBundle {
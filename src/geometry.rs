@@ -3,6 +3,10 @@ use bevy::prelude::*;
 
 pub mod plane;
 pub mod line;
+pub mod triangulation;
+pub mod weld;
+pub mod surface;
+pub mod waves;
 
 pub struct GeometryPlugin;
 
@@ -12,12 +16,20 @@ impl Plugin for GeometryPlugin{
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Plane{
     ///A geometric plane defined using (p - zero_point) dot normal = 0
     pub normal: Vec3,
     pub zero_point: Vec3
 }
 
+impl Default for Plane{
+    fn default() -> Self {
+        Self { normal: Vec3::Y, zero_point: Vec3::ZERO }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Line{
     //A geometric line defined by p = direction * t + zero_point
     pub zero_point: Vec3,
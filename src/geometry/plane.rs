@@ -19,12 +19,32 @@ impl super::Plane{
         return (point.into() - self.zero_point).dot(n)
     }
 
+    pub fn basis(&self) -> (Vec3, Vec3){
+        //two unit vectors spanning the plane, ordered so that u cross v = normal
+        let n = self.normal.normalize();
+        let u = n.any_orthonormal_vector();
+        return (u, n.cross(u));
+    }
+
+    ///Same as [`Plane::basis`] with u running along `axis` laid flat onto the plane,
+    ///an axis standing straight up from the plane falls back on the arbitrary basis.
+    pub fn basis_along(&self, axis: Vec3) -> (Vec3, Vec3){
+        let n = self.normal.normalize();
+        let u = (axis - n * axis.dot(n)).normalize_or_zero();
+        if u == Vec3::ZERO{
+            return self.basis();
+        }
+        return (u, n.cross(u));
+    }
+
     pub fn intersection_from_line(&self, line: super::Line) -> Result<Vec3, PlaneIntersectionError>{
         let divider = line.direction.dot(self.normal);
         let numerator = (self.zero_point - line.zero_point).dot(self.normal);
-        if divider == 0.0{
+        //rounding keeps nearly parallel lines from ever giving exactly zero, compare relative to the lengths involved
+        let scale = line.direction.length() * self.normal.length();
+        if divider.abs() <= f32::EPSILON * scale{
             //line is parallel to plane, no intersection possible
-            if numerator == 0.0 && line.direction.dot(self.normal) == 0.0{
+            if numerator.abs() <= f32::EPSILON * self.normal.length() * (1.0 + (self.zero_point - line.zero_point).length()){
                 //line is fully inside the pane, infinite intersections
                 return Err(PlaneIntersectionError::Enclosed);
             }else{
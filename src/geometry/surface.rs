@@ -0,0 +1,75 @@
+use bevy::{prelude::*, math::Affine3A};
+
+use super::{Line, Plane};
+
+///A liquid surface hulls can be clipped against.
+pub trait LiquidSurface{
+    ///Signed height of a point above the surface, negative when the point is submerged.
+    fn height_above(&self, point: Vec3) -> f32;
+
+    ///Flat plane approximating the surface, caps of clipped hulls are triangulated in it.
+    fn reference_plane(&self) -> Plane;
+
+    ///Point where the segment from `a` to `b` crosses the surface, the ends lie on opposite sides.
+    ///Interpolates the heights of both ends unless the surface knows better.
+    fn crossing(&self, a: Vec3, b: Vec3) -> Option<Vec3>{
+        let height_a = self.height_above(a);
+        let height_b = self.height_above(b);
+        if height_a == height_b{
+            return None;
+        }
+        let t = height_a / (height_a - height_b);
+        return Some(a.lerp(b, t.clamp(0.0, 1.0)));
+    }
+}
+
+impl LiquidSurface for Plane{
+    fn height_above(&self, point: Vec3) -> f32{
+        self.distance_from_plane(point)
+    }
+
+    fn reference_plane(&self) -> Plane{
+        *self
+    }
+
+    fn crossing(&self, a: Vec3, b: Vec3) -> Option<Vec3>{
+        //ends on opposite sides always cross inside the segment, only a degenerate segment fails
+        let point = self.intersection_from_line(Line::from_two_points(&a, &b)).ok()?;
+        let direction = b - a;
+        let t = (point - a).dot(direction) / direction.length_squared();
+        return Some(a + direction * t.clamp(0.0, 1.0));
+    }
+}
+
+///Views a world space surface from a body's local space, so hulls can be clipped where they are.
+pub struct TransformedSurface<'a, S: LiquidSurface + ?Sized>{
+    surface: &'a S,
+    world_from_local: Affine3A,
+    local_from_world: Affine3A
+}
+
+impl<'a, S: LiquidSurface + ?Sized> TransformedSurface<'a, S>{
+    pub fn new(surface: &'a S, world_from_local: Affine3A) -> Self{
+        Self { surface, world_from_local, local_from_world: world_from_local.inverse() }
+    }
+}
+
+impl<'a, S: LiquidSurface + ?Sized> LiquidSurface for TransformedSurface<'a, S>{
+    fn height_above(&self, point: Vec3) -> f32{
+        self.surface.height_above(self.world_from_local.transform_point3(point))
+    }
+
+    fn reference_plane(&self) -> Plane{
+        let plane = self.surface.reference_plane();
+        //normals map with the transpose when going from world to local
+        Plane{
+            zero_point: self.local_from_world.transform_point3(plane.zero_point),
+            normal: (self.world_from_local.matrix3.transpose() * plane.normal).normalize()
+        }
+    }
+
+    fn crossing(&self, a: Vec3, b: Vec3) -> Option<Vec3>{
+        self.surface.crossing(self.world_from_local.transform_point3(a), self.world_from_local.transform_point3(b))
+            .map(|point| self.local_from_world.transform_point3(point))
+    }
+}
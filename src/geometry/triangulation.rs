@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+///Triangulates a set of closed 2d polygon loops using ear clipping.
+///Counter clockwise loops are treated as outer boundaries, clockwise loops as holes that are cut
+///out of the smallest outer boundary containing them. The triangles come out counter clockwise
+///and refer to their corners as (loop index, vertex index) pairs.
+pub fn triangulate_loops(loops: &[Vec<Vec2>]) -> Vec<[(usize, usize); 3]>{
+    let mut outer_loops = Vec::<usize>::new();
+    let mut hole_loops = Vec::<usize>::new();
+    for (loop_index, polygon) in loops.iter().enumerate(){
+        if polygon.len() < 3{
+            continue;
+        }
+        let area = signed_area(polygon);
+        if area > 0.0{
+            outer_loops.push(loop_index);
+        }else if area < 0.0{
+            hole_loops.push(loop_index);
+        }
+    }
+
+    //assign every hole to the smallest outer loop around it
+    let mut holes_of_outer = vec![Vec::<usize>::new(); outer_loops.len()];
+    for hole_index in hole_loops{
+        let test_point = loops[hole_index][0];
+        let mut best: Option<(usize, f32)> = None;
+        for (outer_position, outer_index) in outer_loops.iter().enumerate(){
+            let outer = &loops[*outer_index];
+            if point_in_polygon(test_point, outer){
+                let area = signed_area(outer);
+                if best.map_or(true, |(_, best_area)| area < best_area){
+                    best = Some((outer_position, area));
+                }
+            }
+        }
+        if let Some((outer_position, _)) = best{
+            holes_of_outer[outer_position].push(hole_index);
+        }
+    }
+
+    let mut triangles = Vec::new();
+    for (outer_position, outer_index) in outer_loops.iter().enumerate(){
+        let polygon = bridge_holes(loops, *outer_index, &holes_of_outer[outer_position]);
+        ear_clip(loops, polygon, &mut triangles);
+    }
+    return triangles;
+}
+
+///Signed area of a polygon, positive when its vertices run counter clockwise.
+pub fn signed_area(polygon: &[Vec2]) -> f32{
+    let mut area = 0.0;
+    for i in 0..polygon.len(){
+        area += polygon[i].perp_dot(polygon[(i + 1) % polygon.len()]);
+    }
+    return area / 2.0;
+}
+
+pub fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool{
+    //even-odd rule with a ray cast towards +x
+    let mut inside = false;
+    for i in 0..polygon.len(){
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y){
+            let crossing_x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < crossing_x{
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+fn position(loops: &[Vec<Vec2>], corner: (usize, usize)) -> Vec2{
+    loops[corner.0][corner.1]
+}
+
+fn segments_cross(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> bool{
+    //proper crossings only, touching end points do not count
+    let d1 = (a2 - a1).perp_dot(b1 - a1);
+    let d2 = (a2 - a1).perp_dot(b2 - a1);
+    let d3 = (b2 - b1).perp_dot(a1 - b1);
+    let d4 = (b2 - b1).perp_dot(a2 - b1);
+    return d1 * d2 < 0.0 && d3 * d4 < 0.0;
+}
+
+fn bridge_holes(loops: &[Vec<Vec2>], outer_index: usize, hole_indices: &[usize]) -> Vec<(usize, usize)>{
+    let mut polygon: Vec<(usize, usize)> = (0..loops[outer_index].len()).map(|i| (outer_index, i)).collect();
+
+    //join holes from right to left, each one through its rightmost vertex
+    let mut holes: Vec<(usize, usize)> = hole_indices.iter().map(|hole_index| {
+        let hole = &loops[*hole_index];
+        let mut rightmost = 0;
+        for (i, point) in hole.iter().enumerate(){
+            if point.x > hole[rightmost].x{
+                rightmost = i;
+            }
+        }
+        (*hole_index, rightmost)
+    }).collect();
+    holes.sort_by(|a, b| position(loops, *b).x.total_cmp(&position(loops, *a).x));
+
+    for (hole_pos, (hole_index, rightmost)) in holes.iter().enumerate(){
+        let hole_point = loops[*hole_index][*rightmost];
+
+        //find the closest polygon vertex that can be seen from the hole without crossing any edge
+        let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+        candidates.sort_by(|a, b| {
+            let distance_a = position(loops, polygon[*a]).distance_squared(hole_point);
+            let distance_b = position(loops, polygon[*b]).distance_squared(hole_point);
+            distance_a.total_cmp(&distance_b)
+        });
+
+        let mut bridge = candidates.first().copied();
+        for candidate in candidates{
+            let candidate_point = position(loops, polygon[candidate]);
+            let mut visible = true;
+            for i in 0..polygon.len(){
+                let a = position(loops, polygon[i]);
+                let b = position(loops, polygon[(i + 1) % polygon.len()]);
+                if segments_cross(hole_point, candidate_point, a, b){
+                    visible = false;
+                    break;
+                }
+            }
+            //the holes that are still waiting could be in the way too
+            for (other_index, _) in holes[hole_pos..].iter(){
+                let other = &loops[*other_index];
+                for i in 0..other.len(){
+                    if segments_cross(hole_point, candidate_point, other[i], other[(i + 1) % other.len()]){
+                        visible = false;
+                        break;
+                    }
+                }
+            }
+            if visible{
+                bridge = Some(candidate);
+                break;
+            }
+        }
+
+        let Some(bridge) = bridge else {continue};
+
+        //splice: ..., bridge, hole[rightmost], ..., hole[rightmost - 1], hole[rightmost], bridge, ...
+        let hole_length = loops[*hole_index].len();
+        let mut splice = Vec::with_capacity(hole_length + 2);
+        for i in 0..=hole_length{
+            splice.push((*hole_index, (rightmost + i) % hole_length));
+        }
+        splice.push(polygon[bridge]);
+        polygon.splice(bridge + 1..bridge + 1, splice);
+    }
+
+    return polygon;
+}
+
+fn ear_clip(loops: &[Vec<Vec2>], mut polygon: Vec<(usize, usize)>, triangles: &mut Vec<[(usize, usize); 3]>){
+    while polygon.len() > 3{
+        let count = polygon.len();
+        let mut ear = None;
+        for i in 0..count{
+            let previous = polygon[(i + count - 1) % count];
+            let current = polygon[i];
+            let next = polygon[(i + 1) % count];
+            if is_ear(loops, &polygon, previous, current, next){
+                ear = Some(i);
+                break;
+            }
+        }
+
+        //no clean ear left means the polygon is degenerate, cut the most convex corner and carry on
+        let ear = ear.unwrap_or_else(|| {
+            let mut best = 0;
+            let mut best_turn = f32::NEG_INFINITY;
+            for i in 0..count{
+                let previous = position(loops, polygon[(i + count - 1) % count]);
+                let current = position(loops, polygon[i]);
+                let next = position(loops, polygon[(i + 1) % count]);
+                let turn = (current - previous).perp_dot(next - current);
+                if turn > best_turn{
+                    best_turn = turn;
+                    best = i;
+                }
+            }
+            best
+        });
+
+        let previous = polygon[(ear + count - 1) % count];
+        let next = polygon[(ear + 1) % count];
+        triangles.push([previous, polygon[ear], next]);
+        polygon.remove(ear);
+    }
+
+    if polygon.len() == 3{
+        triangles.push([polygon[0], polygon[1], polygon[2]]);
+    }
+}
+
+fn is_ear(loops: &[Vec<Vec2>], polygon: &[(usize, usize)], previous: (usize, usize), current: (usize, usize), next: (usize, usize)) -> bool{
+    let a = position(loops, previous);
+    let b = position(loops, current);
+    let c = position(loops, next);
+
+    if (b - a).perp_dot(c - b) <= 0.0{
+        //reflex or flat corner
+        return false;
+    }
+
+    for corner in polygon.iter(){
+        if *corner == previous || *corner == current || *corner == next{
+            continue;
+        }
+        let point = position(loops, *corner);
+        //bridge vertices appear twice, their copies sit on the triangle's corners
+        if point == a || point == b || point == c{
+            continue;
+        }
+        if (b - a).perp_dot(point - a) >= 0.0 && (c - b).perp_dot(point - b) >= 0.0 && (a - c).perp_dot(point - c) >= 0.0{
+            return false;
+        }
+    }
+    return true;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn total_area(loops: &[Vec<Vec2>], triangles: &[[(usize, usize); 3]]) -> f32{
+        triangles.iter().map(|triangle| {
+            signed_area(&triangle.map(|corner| position(loops, corner)))
+        }).sum()
+    }
+
+    #[test]
+    fn test_square(){
+        let loops = vec![vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, 1.0)]];
+        let triangles = triangulate_loops(&loops);
+        assert_eq!(triangles.len(), 2);
+        assert!((total_area(&loops, &triangles) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_square_with_hole(){
+        let loops = vec![
+            vec![Vec2::new(-2.0, -2.0), Vec2::new(2.0, -2.0), Vec2::new(2.0, 2.0), Vec2::new(-2.0, 2.0)],
+            //clockwise hole
+            vec![Vec2::new(-1.0, -1.0), Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, -1.0)]
+        ];
+        let triangles = triangulate_loops(&loops);
+        assert_eq!(triangles.len(), 8);
+        assert!((total_area(&loops, &triangles) - 12.0).abs() < 1e-5);
+        for triangle in triangles.iter(){
+            assert!(signed_area(&triangle.map(|corner| position(&loops, corner))) >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use super::{Plane, surface::LiquidSurface};
+
+///Standard gravity, the default for the deep water dispersion relation.
+pub const WAVE_GRAVITY: f32 = 9.81;
+
+///One sinusoidal wave train running across a rest plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaveComponent{
+    pub amplitude: f32,
+    pub wavelength: f32,
+    ///Direction of travel in the rest plane's (u, v) coordinates, u runs along the surface's `axis`, see [`Plane::basis_along`].
+    pub direction: Vec2,
+    pub phase: f32
+}
+
+impl WaveComponent{
+    pub fn wave_number(&self) -> f32{
+        TAU / self.wavelength
+    }
+
+    ///Angular frequency from the deep water dispersion relation under `gravity` (m/s^2).
+    pub fn angular_frequency(&self, gravity: f32) -> f32{
+        (gravity * self.wave_number()).sqrt()
+    }
+
+    ///Phase of the wave at a rest plane position and time, the crest is at 0.
+    pub(crate) fn angle(&self, position: Vec2, time: f32, gravity: f32) -> f32{
+        self.wave_number() * self.direction.normalize().dot(position) - self.angular_frequency(gravity) * time + self.phase
+    }
+}
+
+///Splits points into rest plane coordinates, with u along `axis`, and height above the rest plane.
+pub(crate) fn plane_coordinates(plane: &Plane, axis: Vec3, point: Vec3) -> (Vec2, f32){
+    let (u, v) = plane.basis_along(axis);
+    let offset = point - plane.zero_point;
+    return (Vec2::new(offset.dot(u), offset.dot(v)), offset.dot(plane.normal.normalize()));
+}
+
+///Linear (Airy) waves, the surface height is the sum of the components' cosines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AiryWaves{
+    ///Mean water level, waves move along its basis.
+    pub rest: Plane,
+    ///Direction in the rest plane the wave directions are measured from, it turns with a liquid entity's transform.
+    pub axis: Vec3,
+    pub components: Vec<WaveComponent>,
+    pub time: f32,
+    ///Gravitational acceleration setting how fast each wavelength travels.
+    pub gravity: f32
+}
+
+impl Default for AiryWaves{
+    fn default() -> Self {
+        Self { rest: Plane::default(), axis: Vec3::X, components: Vec::new(), time: 0.0, gravity: WAVE_GRAVITY }
+    }
+}
+
+impl AiryWaves{
+    ///The rest plane's (u, v) basis the wave directions are given in.
+    pub fn basis(&self) -> (Vec3, Vec3){
+        self.rest.basis_along(self.axis)
+    }
+
+    ///Rest plane position of a point and its height above the rest plane.
+    pub(crate) fn plane_coordinates(&self, point: Vec3) -> (Vec2, f32){
+        plane_coordinates(&self.rest, self.axis, point)
+    }
+
+    ///Height of the surface above the rest plane at a rest plane position.
+    pub fn elevation(&self, position: Vec2) -> f32{
+        self.components.iter().map(|wave| wave.amplitude * wave.angle(position, self.time, self.gravity).cos()).sum()
+    }
+}
+
+impl LiquidSurface for AiryWaves{
+    fn height_above(&self, point: Vec3) -> f32{
+        let (position, height) = self.plane_coordinates(point);
+        return height - self.elevation(position);
+    }
+
+    fn reference_plane(&self) -> Plane{
+        self.rest
+    }
+}
+
+///Trochoidal (Gerstner) waves, water particles move in circles so crests come out sharper than Airy waves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GerstnerWaves{
+    pub rest: Plane,
+    ///Direction in the rest plane the wave directions are measured from, it turns with a liquid entity's transform.
+    pub axis: Vec3,
+    pub components: Vec<WaveComponent>,
+    ///From 0 for plain sinusoids up to 1 for the sharpest crests that do not loop over.
+    pub steepness: f32,
+    pub time: f32,
+    ///Gravitational acceleration setting how fast each wavelength travels.
+    pub gravity: f32
+}
+
+impl Default for GerstnerWaves{
+    fn default() -> Self {
+        Self { rest: Plane::default(), axis: Vec3::X, components: Vec::new(), steepness: 0.0, time: 0.0, gravity: WAVE_GRAVITY }
+    }
+}
+
+impl GerstnerWaves{
+    fn horizontal_displacement(&self, position: Vec2) -> Vec2{
+        let count = self.components.len().max(1) as f32;
+        let mut displacement = Vec2::ZERO;
+        for wave in self.components.iter(){
+            let q = self.steepness / (wave.wave_number() * wave.amplitude.abs().max(f32::EPSILON) * count);
+            displacement -= q * wave.amplitude * wave.direction.normalize() * wave.angle(position, self.time, self.gravity).sin();
+        }
+        return displacement;
+    }
+
+    ///Height of the surface above the rest plane at a rest plane position.
+    pub fn elevation(&self, position: Vec2) -> f32{
+        //the surface is parameterised by where particles rest, find the particle that ended up above `position`
+        let mut rest_position = position;
+        for _ in 0..4{
+            rest_position = position - self.horizontal_displacement(rest_position);
+        }
+        return self.components.iter().map(|wave| wave.amplitude * wave.angle(rest_position, self.time, self.gravity).cos()).sum();
+    }
+}
+
+impl LiquidSurface for GerstnerWaves{
+    fn height_above(&self, point: Vec3) -> f32{
+        let (position, height) = plane_coordinates(&self.rest, self.axis, point);
+        return height - self.elevation(position);
+    }
+
+    fn reference_plane(&self) -> Plane{
+        self.rest
+    }
+}
+
+///Surface heights sampled on a regular grid centred on the rest plane's zero point.
+///Heights are shared so copies of the surface stay cheap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightfieldSurface{
+    pub rest: Plane,
+    ///Direction in the rest plane the grid's columns run along, it turns with a liquid entity's transform.
+    pub axis: Vec3,
+    ///Extent of the grid along the rest plane's (u, v) basis.
+    pub size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    ///Row major heights above the rest plane, `columns * rows` of them.
+    pub heights: Arc<Vec<f32>>
+}
+
+impl Default for HeightfieldSurface{
+    fn default() -> Self {
+        Self { rest: Plane::default(), axis: Vec3::X, size: Vec2::ZERO, columns: 0, rows: 0, heights: Arc::default() }
+    }
+}
+
+impl HeightfieldSurface{
+    ///Bilinearly interpolated height above the rest plane, positions off the grid use the closest edge.
+    pub fn elevation(&self, position: Vec2) -> f32{
+        if self.columns == 0 || self.rows == 0 || self.heights.len() < self.columns * self.rows{
+            return 0.0;
+        }
+        let cells = Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32);
+        let grid_position = ((position / self.size + 0.5) * cells).clamp(Vec2::ZERO, cells);
+
+        let column = (grid_position.x.floor() as usize).min(self.columns.saturating_sub(2));
+        let row = (grid_position.y.floor() as usize).min(self.rows.saturating_sub(2));
+        let fraction = grid_position - Vec2::new(column as f32, row as f32);
+
+        let sample = |column: usize, row: usize| self.heights[row.min(self.rows - 1) * self.columns + column.min(self.columns - 1)];
+        let bottom = sample(column, row) * (1.0 - fraction.x) + sample(column + 1, row) * fraction.x;
+        let top = sample(column, row + 1) * (1.0 - fraction.x) + sample(column + 1, row + 1) * fraction.x;
+        return bottom * (1.0 - fraction.y) + top * fraction.y;
+    }
+}
+
+impl LiquidSurface for HeightfieldSurface{
+    fn height_above(&self, point: Vec3) -> f32{
+        let (position, height) = plane_coordinates(&self.rest, self.axis, point);
+        return height - self.elevation(position);
+    }
+
+    fn reference_plane(&self) -> Plane{
+        self.rest
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_airy_elevation(){
+        let waves = AiryWaves{
+            components: vec![WaveComponent{amplitude: 0.5, wavelength: 10.0, direction: Vec2::X, phase: 0.0}],
+            ..default()
+        };
+        let (u, _) = waves.basis();
+
+        //crest at the origin, trough half a wavelength along the direction of travel
+        assert!((waves.height_above(Vec3::ZERO) + 0.5).abs() < 1e-5);
+        assert!((waves.height_above(u * 5.0) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_gerstner_keeps_crest_height(){
+        let waves = GerstnerWaves{
+            components: vec![WaveComponent{amplitude: 0.5, wavelength: 10.0, direction: Vec2::X, phase: 0.0}],
+            steepness: 0.8,
+            ..default()
+        };
+        assert!((waves.elevation(Vec2::ZERO) - 0.5).abs() < 1e-4);
+        assert!((waves.elevation(Vec2::new(5.0, 0.0)) + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_heightfield_interpolation(){
+        let field = HeightfieldSurface{
+            size: Vec2::new(2.0, 2.0),
+            columns: 2,
+            rows: 2,
+            heights: Arc::new(vec![0.0, 1.0, 0.0, 1.0]),
+            ..default()
+        };
+        assert!((field.elevation(Vec2::ZERO) - 0.5).abs() < 1e-5);
+        assert!((field.elevation(Vec2::new(1.0, 0.0)) - 1.0).abs() < 1e-5);
+        //off the grid the edge height carries on
+        assert!((field.elevation(Vec2::new(5.0, 0.0)) - 1.0).abs() < 1e-5);
+    }
+}
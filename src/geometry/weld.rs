@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+///Merges points that lie within a tolerance of each other.
+///Points are bucketed into a grid of tolerance sized cells so lookups only check the neighbouring cells.
+pub struct PointWelder<T: Copy>{
+    tolerance: f32,
+    cells: HashMap<IVec3, Vec<(Vec3, T)>>
+}
+
+impl<T: Copy> PointWelder<T>{
+    pub fn new(tolerance: f32) -> Self{
+        Self { tolerance: tolerance.max(f32::MIN_POSITIVE), cells: HashMap::new() }
+    }
+
+    ///Returns the value of an earlier point within tolerance, or stores and returns the new value.
+    pub fn weld(&mut self, position: Vec3, value: T) -> T{
+        let cell = (position / self.tolerance).floor().as_ivec3();
+        for x in -1..=1{
+            for y in -1..=1{
+                for z in -1..=1{
+                    //tiny tolerances put far away points in saturated cells, wrap rather than overflow next to them
+                    if let Some(points) = self.cells.get(&cell.wrapping_add(IVec3::new(x, y, z))){
+                        for (other_position, other_value) in points.iter(){
+                            if other_position.distance(position) <= self.tolerance{
+                                return *other_value;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.cells.entry(cell).or_default().push((position, value));
+        return value;
+    }
+}
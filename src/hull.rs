@@ -3,11 +3,18 @@ use std::sync::Arc;
 
 pub mod hull_shape;
 pub mod clipping;
+pub mod mass_properties;
+pub mod waterplane;
+pub mod hydrostatics;
+pub mod equilibrium;
+pub mod sync;
+pub mod debug;
 
 pub struct HullPlugin;
 
 impl Plugin for HullPlugin{
-    fn build(&self, _app: &mut App) {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(sync::HullSyncPlugin);
     }
 }
 
@@ -17,6 +24,28 @@ pub struct Hull{
     shape: Arc<hull_shape::HullShape>
 }
 
+impl Hull{
+    ///Builds a hull from a triangle mesh, merging vertices within `weld_tolerance` of each other.
+    pub fn from_mesh(mesh: &Mesh, weld_tolerance: f32) -> Result<Self, hull_shape::into::HullShapeIntoError>{
+        return Ok(Hull { shape: Arc::new(hull_shape::HullShape::from_mesh(mesh, weld_tolerance)?) });
+    }
+
+    ///Same as [`Hull::from_mesh`], failing unless the mesh makes a watertight hull.
+    pub fn from_mesh_validated(mesh: &Mesh, weld_tolerance: f32) -> Result<Self, hull_shape::into::HullShapeIntoError>{
+        return Ok(Hull { shape: Arc::new(hull_shape::HullShape::from_mesh_validated(mesh, weld_tolerance)?) });
+    }
+
+    pub fn shape(&self) -> &hull_shape::HullShape{
+        &self.shape
+    }
+}
+
+impl From<hull_shape::HullShape> for Hull{
+    fn from(shape: hull_shape::HullShape) -> Self {
+        Hull { shape: Arc::new(shape) }
+    }
+}
+
 impl TryFrom <Mesh> for Hull {
     type Error = hull_shape::into::HullShapeIntoError;
 
@@ -25,9 +54,18 @@ impl TryFrom <Mesh> for Hull {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ClippedHull{
     shape: Arc<hull_shape::HullShape>,
     indices: Vec<clipping::ClippedIndex>,
-    patch_vertices: Vec<Vec3>
+    patch_vertices: Vec<Vec3>,
+    plane: crate::geometry::Plane,
+    //closed loops of the cut outline, running counter clockwise around the plane normal
+    cap_loops: Vec<Vec<clipping::ClippedIndex>>,
+    //triangulation of the cap loops, faces along the plane normal
+    cap_indices: Vec<clipping::ClippedIndex>,
+    //faces closing the cuts against bounds, see ClippedHull::clip_with_bounds
+    wall_indices: Vec<clipping::ClippedIndex>,
+    //distance the hull was clipped with, later cuts against bounds use the same
+    tolerance: f32
 }
\ No newline at end of file
@@ -1,160 +1,481 @@
-use crate::geometry::Line;
+use crate::geometry::{surface::LiquidSurface, triangulation, weld::PointWelder};
+use std::collections::{BTreeMap, HashMap};
 
 use super::*;
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Eq, Hash, PartialEq, Copy, Clone)]
+///Fraction of a hull's size within which vertices lie on a cutting surface, see [`ClipTolerance::default`].
+pub const DEFAULT_CLIP_TOLERANCE: f32 = 1e-5;
+
+///How close a vertex has to be to a cutting surface to lie on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClipTolerance{
+    ///A distance in the hull's local units.
+    Absolute(f32),
+    ///A fraction of the diagonal of the hull's bounding box, so it holds up whatever the hull's scale.
+    Relative(f32)
+}
+
+impl Default for ClipTolerance{
+    fn default() -> Self {
+        Self::Relative(DEFAULT_CLIP_TOLERANCE)
+    }
+}
+
+impl ClipTolerance{
+    ///The tolerance as a distance in the shape's units.
+    pub fn distance(&self, shape: &hull_shape::HullShape) -> f32{
+        match self{
+            ClipTolerance::Absolute(distance) => *distance,
+            ClipTolerance::Relative(fraction) => {
+                if shape.vertices.is_empty(){
+                    return 0.0;
+                }
+                let mut min = Vec3::splat(f32::INFINITY);
+                let mut max = Vec3::splat(f32::NEG_INFINITY);
+                for vertex in shape.vertices.iter(){
+                    min = min.min(vertex.position);
+                    max = max.max(vertex.position);
+                }
+                fraction * (max - min).length()
+            }
+        }
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Copy, Clone, Debug)]
 pub enum ClippedIndex{
     OriginalIndex(usize),
     PatchIndex(usize),
 }
 
-#[derive(PartialEq)]
-enum ProcessedEdge{
-    FullySubmerged,
-    FullyClipped,
-    PartialClip{
-        original_vertex_index:usize,
-        patch_vertex_index:usize
+///Which side of a surface a vertex is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side{
+    Below,
+    ///Within the tolerance of the surface, the vertex belongs to both parts and the cut runs through it.
+    On,
+    Above
+}
+
+impl Side{
+    pub fn classify(height_above: f32, tolerance: f32) -> Self{
+        if height_above < -tolerance{
+            return Side::Below;
+        }else if height_above > tolerance{
+            return Side::Above;
+        }
+        return Side::On;
+    }
+}
+
+//corner of a cut face, flagged when it lies on the cut
+type CutCorner = (ClippedIndex, bool);
+
+//Splits a triangle by the sides of its corners, `crossing(i)` gives the cut on the edge from corner i to the next.
+//Corners on the surface go to both parts, a triangle lying in the surface goes to the part it faces away from.
+fn split_triangle(
+    corners: [ClippedIndex;3],
+    sides: [Side;3],
+    faces_up: impl FnOnce() -> bool,
+    mut crossing: impl FnMut(usize) -> Option<ClippedIndex>,
+    below: &mut Vec<CutCorner>,
+    above: &mut Vec<CutCorner>
+){
+    below.clear();
+    above.clear();
+
+    if sides.iter().all(|side| *side == Side::On){
+        let polygon = if faces_up() {below} else {above};
+        polygon.extend(corners.map(|corner| (corner, true)));
+        return;
+    }
+
+    for i in 0..3{
+        match sides[i]{
+            Side::Below => below.push((corners[i], false)),
+            Side::Above => above.push((corners[i], false)),
+            Side::On => {
+                below.push((corners[i], true));
+                above.push((corners[i], true));
+            }
+        }
+        if let (Side::Below, Side::Above) | (Side::Above, Side::Below) = (sides[i], sides[(i+1)%3]){
+            //an unsuccessful split leaves the edge whole, the outline then skips the crossing
+            if let Some(patch) = crossing(i){
+                below.push((patch, true));
+                above.push((patch, true));
+            }
+        }
+    }
+}
+
+fn triangulate_polygon(polygon: &[CutCorner], indices: &mut Vec<ClippedIndex>){
+    //the pieces of a cut triangle are convex, a fan covers them
+    for i in 2..polygon.len(){
+        indices.extend_from_slice(&[polygon[0].0, polygon[i-1].0, polygon[i].0]);
+    }
+}
+
+fn push_cut_segments(polygon: &[CutCorner], segments: &mut Vec<(ClippedIndex, ClippedIndex)>){
+    //the face runs along the cut from one cut corner to the next, the cap has to run back the other way
+    if polygon.len() < 3{
+        return;
+    }
+    for i in 0..polygon.len(){
+        let (start, start_on_cut) = polygon[i];
+        let (end, end_on_cut) = polygon[(i+1)%polygon.len()];
+        if start_on_cut && end_on_cut{
+            segments.push((end, start));
+        }
     }
 }
 
+///The parts of a hull below and above a surface, see [`Hull::split_with_surface`].
+pub struct SplitHull{
+    pub submerged: ClippedHull,
+    ///Clipped with the surface turned over, so its plane and cap face down into the liquid.
+    pub emerged: ClippedHull
+}
+
 
 impl Hull{
     pub fn clip_with_plane(&self, plane: &crate::geometry::Plane) -> ClippedHull{
         //Returns the sliced hull below the cut plane
+        self.clip_with_surface(plane)
+    }
+
+    ///Keeps the part of the hull inside a convex volume, given by planes with their normals pointing out of it.
+    ///Each plane clips what the ones before it left, crossings are shared between neighbouring faces
+    ///so every pass can cut the patch vertices of the passes before. All cuts end up as wall faces,
+    ///there is no cap, and the first plane becomes the hull's [`ClippedHull::plane`].
+    pub fn clip_with_convex(&self, planes: &[crate::geometry::Plane]) -> ClippedHull{
+        self.clip_convex_with_tolerance(planes, ClipTolerance::default())
+    }
+
+    ///Same as [`Hull::clip_with_convex`], vertices within `tolerance` of a plane are taken to lie on it.
+    pub fn clip_convex_with_tolerance(&self, planes: &[crate::geometry::Plane], tolerance: ClipTolerance) -> ClippedHull{
+        let whole = ClippedHull{
+            shape: self.shape.clone(),
+            indices: self.shape.faces.iter().flat_map(|face| face.vertex_indices.map(ClippedIndex::OriginalIndex)).collect(),
+            plane: planes.first().copied().unwrap_or_default(),
+            tolerance: tolerance.distance(&self.shape),
+            ..Default::default()
+        };
+        return whole.clip_with_bounds(planes);
+    }
+
+    pub fn clip_with_surface<S: LiquidSurface + ?Sized>(&self, surface: &S) -> ClippedHull{
+        //Returns the part of the hull below the surface, vertices are classified by their height above
+        //the surface right where they are, so waves and heightfields cut the hull along their shape
+        return self.clip_with_tolerance(surface, ClipTolerance::default());
+    }
+
+    ///Same as [`Hull::clip_with_surface`], vertices within `tolerance` of the surface are taken to lie on it.
+    pub fn clip_with_tolerance<S: LiquidSurface + ?Sized>(&self, surface: &S, tolerance: ClipTolerance) -> ClippedHull{
+        return self.split(surface, tolerance, false).submerged;
+    }
+
+    ///Both parts of the hull from a single pass, they share their patch vertices and cap.
+    pub fn split_with_plane(&self, plane: &crate::geometry::Plane) -> SplitHull{
+        self.split(plane, ClipTolerance::default(), true)
+    }
+
+    ///Both parts of the hull from a single pass, they share their patch vertices and cap.
+    pub fn split_with_surface<S: LiquidSurface + ?Sized>(&self, surface: &S) -> SplitHull{
+        self.split(surface, ClipTolerance::default(), true)
+    }
+
+    ///Same as [`Hull::split_with_surface`], vertices within `tolerance` of the surface are taken to lie on it.
+    pub fn split_with_tolerance<S: LiquidSurface + ?Sized>(&self, surface: &S, tolerance: ClipTolerance) -> SplitHull{
+        self.split(surface, tolerance, true)
+    }
+
+    fn split<S: LiquidSurface + ?Sized>(&self, surface: &S, tolerance: ClipTolerance, keep_emerged: bool) -> SplitHull{
         let shape = self.shape.as_ref();
+        let tolerance = tolerance.distance(shape);
 
         //contains the result
-        let mut result = ClippedHull{shape: self.shape.clone(), ..Default::default()};
+        let mut submerged = ClippedHull{shape: self.shape.clone(), plane: surface.reference_plane(), tolerance, ..Default::default()};
+        let up = submerged.plane.normal;
 
-        //used later during face processing, initializes all to 0
-        let mut  face_submerged_vertices_count = Vec::<u8>::new();
-        face_submerged_vertices_count.resize_with(shape.faces.len(), ||{0 as u8});
-        
         //----Vertex Processing
-        let mut submerged_vertices = Vec::<bool>::with_capacity(self.shape.vertices.len());
-        for vertex in self.shape.vertices.iter(){
-            if plane.distance_from_plane(vertex.position) < 0.0{
-                submerged_vertices.push(true);
+        let sides: Vec<Side> = shape.vertices.iter().map(|vertex| Side::classify(surface.height_above(vertex.position), tolerance)).collect();
 
-                for face_index in vertex.face_indices.iter(){
-                    //tracks how many vertices of a face are submerged, used during face processing
-                    *face_submerged_vertices_count.get_mut(*face_index).unwrap() += 1;
+        // --- Edge Processing
+        //patch vertex where an edge crosses from one side to the other, vertices on the surface are never split
+        let mut edge_patches = Vec::<Option<usize>>::with_capacity(shape.edges.len());
+        for edge in shape.edges.iter(){
+            let [vertex_1_index, vertex_2_index] = edge.vertex_indexes;
+            let crosses = matches!((sides[vertex_1_index], sides[vertex_2_index]), (Side::Below, Side::Above) | (Side::Above, Side::Below));
+            if !crosses{
+                edge_patches.push(None);
+                continue;
+            }
+            let intersection = surface.crossing(shape.vertices[vertex_1_index].position, shape.vertices[vertex_2_index].position);
+            edge_patches.push(intersection.map(|point| {
+                submerged.patch_vertices.push(point);
+                submerged.patch_vertices.len() - 1
+            }));
+        }
 
+        // --- Face Processing
+        let mut emerged_indices = Vec::<ClippedIndex>::new();
+        //pieces of the cut outline, one per face touching the surface
+        let mut cap_segments = Vec::<(ClippedIndex, ClippedIndex)>::new();
+        let mut submerged_polygon = Vec::<CutCorner>::with_capacity(4);
+        let mut emerged_polygon = Vec::<CutCorner>::with_capacity(4);
+
+        for face in shape.faces.iter(){
+            let face_sides = face.vertex_indices.map(|index| sides[index]);
+            if face_sides.iter().all(|side| *side == Side::Below){
+                submerged.indices.extend(face.vertex_indices.map(ClippedIndex::OriginalIndex));
+                continue;
+            }else if face_sides.iter().all(|side| *side == Side::Above){
+                if keep_emerged{
+                    emerged_indices.extend(face.vertex_indices.map(ClippedIndex::OriginalIndex));
                 }
-            }else{
-                submerged_vertices.push(false);
+                continue;
             }
 
+            split_triangle(
+                face.vertex_indices.map(ClippedIndex::OriginalIndex),
+                face_sides,
+                || {
+                    let [a, b, c] = face.vertex_indices.map(|index| shape.vertices[index].position);
+                    (b - a).cross(c - a).dot(up) > 0.0
+                },
+                |i| edge_patches[face.edge_indexes[i]].map(ClippedIndex::PatchIndex),
+                &mut submerged_polygon,
+                &mut emerged_polygon
+            );
+
+            push_cut_segments(&submerged_polygon, &mut cap_segments);
+            triangulate_polygon(&submerged_polygon, &mut submerged.indices);
+            if keep_emerged{
+                triangulate_polygon(&emerged_polygon, &mut emerged_indices);
+            }
         }
 
-        // --- Edge Processing
-        let mut processed_edges: Vec<ProcessedEdge> = Vec::with_capacity(self.shape.edges.len());
-        for edge in self.shape.edges.iter(){
-            let [vertex_1_index,vertex_2_index] = edge.vertex_indexes;
-            let vertex_1 = &shape.vertices[vertex_1_index];
-            let vertex_2 = &shape.vertices[vertex_2_index];
-
-            if submerged_vertices[vertex_1_index] ^ submerged_vertices[vertex_2_index]{
-                //This edge is split between the plane
-                
-                let edge_line = Line::from_two_points(&vertex_1.position, &vertex_2.position);
-                let intersection = plane.intersection_from_line(edge_line);
-                if let Ok(point) = intersection{
-                    let patch_index = result.patch_vertices.len();
-                    result.patch_vertices.push(point.clone());
-                    if submerged_vertices[vertex_1_index]{
-                        processed_edges.push(ProcessedEdge::PartialClip { original_vertex_index: vertex_1_index, patch_vertex_index: patch_index });
-                    }else{
-                        processed_edges.push(ProcessedEdge::PartialClip { original_vertex_index: vertex_2_index, patch_vertex_index: patch_index });
-                    }
-                }else{
-                    //Split unsuccessful, assume edge runs parallel and inside plane
-                    processed_edges.push(ProcessedEdge::FullySubmerged);
-                }
+        submerged.close_cap(&cap_segments);
+
+        if !keep_emerged{
+            return SplitHull{submerged, emerged: ClippedHull::default()};
+        }
+
+        //the emerged part shares the cap, seen from the other side
+        let emerged = ClippedHull{
+            shape: self.shape.clone(),
+            indices: emerged_indices,
+            patch_vertices: submerged.patch_vertices.clone(),
+            plane: crate::geometry::Plane{normal: -submerged.plane.normal, zero_point: submerged.plane.zero_point},
+            cap_loops: submerged.cap_loops.iter().map(|cap_loop| cap_loop.iter().rev().copied().collect()).collect(),
+            cap_indices: submerged.cap_indices.chunks_exact(3).flat_map(|triangle| [triangle[0], triangle[2], triangle[1]]).collect(),
+            wall_indices: Vec::new(),
+            tolerance
+        };
+        return SplitHull{submerged, emerged};
+    }
+}
+
+impl ClippedHull{
+    fn close_cap(&mut self, cap_segments: &[(ClippedIndex, ClippedIndex)]){
+        //Closes the cut against the surface so the clipped hull is closed
+        let (cap_loops, cap_indices) = self.close_loops(cap_segments, &self.plane);
+        self.cap_loops = cap_loops;
+        self.cap_indices = cap_indices;
+    }
+
+    fn chain_loops(&self, segments: &[(ClippedIndex, ClippedIndex)]) -> Vec<Vec<ClippedIndex>>{
+        //Links directed segments into closed loops, a segment and its reverse cancel out
 
+        //neighbouring faces each make their own copy of a shared patch vertex, merge them by position
+        let mut welder = PointWelder::<ClippedIndex>::new(self.tolerance);
 
-            }else if submerged_vertices[vertex_1_index] & submerged_vertices[vertex_2_index]{
-                processed_edges.push(ProcessedEdge::FullySubmerged);
-            }else{
-                processed_edges.push(ProcessedEdge::FullyClipped);
+        //a segment and its reverse come from faces lying in the cut or touching it from the same side, they cancel out
+        let mut net_segments = BTreeMap::<(ClippedIndex, ClippedIndex), i32>::new();
+        for (start, end) in segments.iter(){
+            let start = welder.weld(self.position(*start), *start);
+            let end = welder.weld(self.position(*end), *end);
+            if start < end{
+                *net_segments.entry((start, end)).or_default() += 1;
+            }else if end < start{
+                *net_segments.entry((end, start)).or_default() -= 1;
             }
         }
 
-        // --- Face Processing
-        let mut current_face_poly_line_indices = Vec::<ClippedIndex>::with_capacity(4);
-        
-        for (face_index, face) in shape.faces.iter().enumerate(){
-            let submerged_vertices_count = *face_submerged_vertices_count.get(face_index).unwrap();
-            
-            if submerged_vertices_count == 1 || submerged_vertices_count == 2 {
-                current_face_poly_line_indices.clear();
-                //generate the poly line
-                
-                for i in 0..3{
-                    let current_vertex_index = face.vertex_indices[i];
-                    let current_edge_index = face.edge_indexes[i];
-                    let previous_edge_index = face.edge_indexes[(i+2)%3];
-                    let next_edge_index = face.edge_indexes[(i+1)%3];
-
-                    let current_processed_edge = &processed_edges[current_edge_index];
-
-                    match current_processed_edge{
-                        ProcessedEdge::FullySubmerged=>{
-                            current_face_poly_line_indices.push(ClippedIndex::OriginalIndex(current_vertex_index));
-                        },
-                        ProcessedEdge::PartialClip { original_vertex_index:_, patch_vertex_index } =>{
-                            if submerged_vertices[current_vertex_index]{
-                                current_face_poly_line_indices.push(ClippedIndex::OriginalIndex(current_vertex_index));
-                                if let ProcessedEdge::PartialClip { original_vertex_index:_, patch_vertex_index:_ } = processed_edges[next_edge_index]{
-                                    current_face_poly_line_indices.push(ClippedIndex::PatchIndex(*patch_vertex_index));
-                                }
-                            }else{
-                                current_face_poly_line_indices.push(ClippedIndex::PatchIndex(*patch_vertex_index));
-                                
-                            }
-                        },
-                        ProcessedEdge::FullyClipped =>{
-                            if let ProcessedEdge::PartialClip { original_vertex_index:_, patch_vertex_index } = processed_edges[previous_edge_index]{
-                                current_face_poly_line_indices.push(ClippedIndex::PatchIndex(patch_vertex_index));
-                            }
-                        }
+        let mut next_vertices = HashMap::<ClippedIndex, Vec<ClippedIndex>>::new();
+        for ((low, high), count) in net_segments{
+            let (start, end) = if count > 0 {(low, high)} else {(high, low)};
+            for _ in 0..count.abs(){
+                next_vertices.entry(start).or_default().push(end);
+            }
+        }
+
+        //walk the segments into closed loops, anything that does not close is dropped
+        let mut loops = Vec::<Vec<ClippedIndex>>::new();
+        let mut starts: Vec<ClippedIndex> = next_vertices.keys().copied().collect();
+        starts.sort();
+        for start in starts{
+            while next_vertices.get(&start).is_some_and(|next| !next.is_empty()){
+                let mut cap_loop = vec![start];
+                let mut current = start;
+                let mut closed = false;
+                while let Some(next) = next_vertices.get_mut(&current).and_then(|next| next.pop()){
+                    if next == start{
+                        closed = true;
+                        break;
                     }
+                    cap_loop.push(next);
+                    current = next;
+                }
+                if closed && cap_loop.len() >= 3{
+                    loops.push(cap_loop);
                 }
+            }
+        }
+        return loops;
+    }
+
+    fn close_loops(&self, segments: &[(ClippedIndex, ClippedIndex)], plane: &crate::geometry::Plane) -> (Vec<Vec<ClippedIndex>>, Vec<ClippedIndex>){
+        //Chains the cut segments into loops and triangulates them in the plane
+        let loops = self.chain_loops(segments);
 
+        //triangulate in the plane, counter clockwise in (u, v) faces along the normal
+        let (u, v) = plane.basis();
+        let projected_loops: Vec<Vec<Vec2>> = loops.iter().map(|cap_loop| {
+            cap_loop.iter().map(|index| {
+                let offset = self.position(*index) - plane.zero_point;
+                Vec2::new(offset.dot(u), offset.dot(v))
+            }).collect()
+        }).collect();
+
+        let mut indices = Vec::new();
+        for triangle in triangulation::triangulate_loops(&projected_loops){
+            for (loop_index, vertex_index) in triangle{
+                indices.push(loops[loop_index][vertex_index]);
             }
+        }
+        return (loops, indices);
+    }
 
-            
+    ///Cuts away everything outside a convex volume, given by planes with their normals pointing out of it.
+    ///The cuts are closed with wall faces, the cap stays the part of the surface inside the volume.
+    ///Vertices are classified with the tolerance the hull was clipped with.
+    pub fn clip_with_bounds(&self, planes: &[crate::geometry::Plane]) -> ClippedHull{
+        let mut result = self.clone();
+        for plane in planes.iter(){
+            result = result.clip_with_bound(plane);
+        }
+        return result;
+    }
 
-            match submerged_vertices_count{
-                1 => {
-                    assert_eq!(current_face_poly_line_indices.len(),3);
-                    for new_index in current_face_poly_line_indices.iter(){
-                        result.indices.push(*new_index);
-                    }
-                },
-                2 => {
-                    //four side face with two patch vertices
-                    result.indices.push(current_face_poly_line_indices[0]);
-                    result.indices.push(current_face_poly_line_indices[1]);
-                    result.indices.push(current_face_poly_line_indices[2]);
-
-                    result.indices.push(current_face_poly_line_indices[0]);
-                    result.indices.push(current_face_poly_line_indices[2]);
-                    result.indices.push(current_face_poly_line_indices[3]);
-                },
-                3 => {
-                    //whole face is submerged
-                    for vertices_index in face.vertex_indices{
-                        result.indices.push(ClippedIndex::OriginalIndex(vertices_index));
-                    }
-                },
-                _ => () //default case, probably completely clipped
+    fn clip_with_bound(&self, plane: &crate::geometry::Plane) -> ClippedHull{
+        let mut result = ClippedHull{
+            shape: self.shape.clone(),
+            patch_vertices: self.patch_vertices.clone(),
+            plane: self.plane,
+            tolerance: self.tolerance,
+            ..Default::default()
+        };
+
+        let mut sides = HashMap::<ClippedIndex, (f32, Side)>::new();
+        //crossings are shared by the faces on both sides of an edge
+        let mut crossings = HashMap::<(ClippedIndex, ClippedIndex), ClippedIndex>::new();
+        let mut wall_segments = Vec::<(ClippedIndex, ClippedIndex)>::new();
+        let mut clipped_anything = false;
+        let mut inside_polygon = Vec::<CutCorner>::with_capacity(4);
+        let mut outside_polygon = Vec::<CutCorner>::with_capacity(4);
+
+        let face_lists = [&self.indices, &self.cap_indices, &self.wall_indices];
+        let mut clipped_lists: [Vec<ClippedIndex>; 3] = Default::default();
+        for (faces, clipped_faces) in face_lists.into_iter().zip(clipped_lists.iter_mut()){
+            for triangle in faces.chunks_exact(3){
+                let corners = [triangle[0], triangle[1], triangle[2]];
+                let [distance_and_side_0, distance_and_side_1, distance_and_side_2] = corners.map(|corner| {
+                    *sides.entry(corner).or_insert_with(|| {
+                        let distance = plane.distance_from_plane(self.position(corner));
+                        (distance, Side::classify(distance, self.tolerance))
+                    })
+                });
+                let distances = [distance_and_side_0.0, distance_and_side_1.0, distance_and_side_2.0];
+                let triangle_sides = [distance_and_side_0.1, distance_and_side_1.1, distance_and_side_2.1];
+                if triangle_sides.iter().all(|side| *side == Side::Below){
+                    clipped_faces.extend_from_slice(triangle);
+                    continue;
+                }
+                if triangle_sides.iter().any(|side| *side == Side::Above){
+                    clipped_anything = true;
+                }
+
+                split_triangle(
+                    corners,
+                    triangle_sides,
+                    || {
+                        let [a, b, c] = corners.map(|corner| self.position(corner));
+                        (b - a).cross(c - a).dot(plane.normal) > 0.0
+                    },
+                    |i| {
+                        let (start, end) = (corners[i], corners[(i+1)%3]);
+                        let (start_distance, end_distance) = (distances[i], distances[(i+1)%3]);
+                        let key = if start < end {(start, end)} else {(end, start)};
+                        let crossing = *crossings.entry(key).or_insert_with(|| {
+                            let t = start_distance / (start_distance - end_distance);
+                            result.patch_vertices.push(self.position(start).lerp(self.position(end), t));
+                            ClippedIndex::PatchIndex(result.patch_vertices.len() - 1)
+                        });
+                        Some(crossing)
+                    },
+                    &mut inside_polygon,
+                    &mut outside_polygon
+                );
+
+                push_cut_segments(&inside_polygon, &mut wall_segments);
+                triangulate_polygon(&inside_polygon, clipped_faces);
             }
         }
+
+        let [indices, cap_indices, mut wall_indices] = clipped_lists;
+        if clipped_anything{
+            let (_, new_wall_indices) = result.close_loops(&wall_segments, plane);
+            wall_indices.extend(new_wall_indices);
+
+            //the cap's outline now runs along the bound where it was cut, trace it again around what is left
+            let cap_edges: Vec<(ClippedIndex, ClippedIndex)> = cap_indices.chunks_exact(3)
+                .flat_map(|triangle| [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])])
+                .collect();
+            result.cap_loops = result.chain_loops(&cap_edges);
+        }else{
+            result.cap_loops = self.cap_loops.clone();
+        }
+        result.indices = indices;
+        result.cap_indices = cap_indices;
+        result.wall_indices = wall_indices;
         return result;
     }
-}
\ No newline at end of file
+
+    ///Plane the hull was clipped with.
+    pub fn plane(&self) -> &crate::geometry::Plane{
+        &self.plane
+    }
+
+    ///Faces of the original hull that remain after clipping, without the cap.
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3;3]> + '_{
+        self.indices.chunks_exact(3).map(|triangle| [self.position(triangle[0]), self.position(triangle[1]), self.position(triangle[2])])
+    }
+
+    ///Faces closing the cut, they face along the plane normal.
+    ///Bounds clip it like the rest of the hull, so it stays the part of the surface inside them.
+    pub fn cap_triangles(&self) -> impl Iterator<Item = [Vec3;3]> + '_{
+        self.cap_indices.chunks_exact(3).map(|triangle| [self.position(triangle[0]), self.position(triangle[1]), self.position(triangle[2])])
+    }
+
+    pub(super) fn position(&self, index: ClippedIndex) -> Vec3{
+        match index{
+            ClippedIndex::OriginalIndex(index) => self.shape.vertices[index].position,
+            ClippedIndex::PatchIndex(index) => self.patch_vertices[index]
+        }
+    }
+}
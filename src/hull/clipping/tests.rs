@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::hull::*;
+use crate::hull::clipping::{ClippedIndex, ClipTolerance};
 use crate::geometry::*;
 
 #[test]
@@ -24,4 +25,210 @@ fn test_clipping(){
     let result = hull.clip_with_plane(&high_plane);
 
     assert_eq!(result.indices.len(), initial_mesh.indices().unwrap().len(), "Result should have same amount of indices as original.")
-}
\ No newline at end of file
+}
+fn closed_volume_about(clipped: &ClippedHull, apex: Vec3) -> f32{
+    //only a closed shell gives the same volume for every apex
+    let mut integral = mass_properties::VolumeIntegral::new(apex);
+    for triangle in clipped.indices.chunks_exact(3).chain(clipped.cap_indices.chunks_exact(3)).chain(clipped.wall_indices.chunks_exact(3)){
+        integral.add_triangle(clipped.position(triangle[0]), clipped.position(triangle[1]), clipped.position(triangle[2]));
+    }
+    return integral.volume();
+}
+
+fn cap_area(clipped: &ClippedHull) -> f32{
+    let mut area = 0.0;
+    for triangle in clipped.cap_indices.chunks_exact(3){
+        let a = clipped.position(triangle[0]);
+        let b = clipped.position(triangle[1]);
+        let c = clipped.position(triangle[2]);
+        //signed along the plane normal, flipped triangles would cancel out
+        area += (b - a).cross(c - a).dot(clipped.plane.normal) / 2.0;
+    }
+    return area;
+}
+
+#[test]
+fn test_cube_cap(){
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+
+    let clipped = hull.clip_with_plane(&Plane{
+        normal: Vec3::new(0.1, 1.0, 0.0).normalize(),
+        zero_point: Vec3::new(0.0, 0.3, 0.0)
+    });
+
+    assert_eq!(clipped.cap_loops.len(), 1);
+    let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&clipped, Vec3::new(5.0, -3.0, 2.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-4, "Clipped cube is not closed.");
+    assert!(cap_area(&clipped) > 4.0);
+}
+
+#[test]
+fn test_torus_cap_with_hole(){
+    let hull = Hull::try_from(Mesh::from(shape::Torus::default())).unwrap();
+
+    let clipped = hull.clip_with_plane(&Plane{
+        normal: Vec3::Y,
+        zero_point: Vec3::new(0.0, 0.01, 0.0)
+    });
+
+    //an outer ring and the hole in the middle
+    assert_eq!(clipped.cap_loops.len(), 2);
+
+    let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&clipped, Vec3::new(0.0, -4.0, 1.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-3, "Clipped torus is not closed.");
+
+    //annulus between the ring radii of the default torus
+    let expected_area = std::f32::consts::PI * (1.5 * 1.5 - 0.5 * 0.5);
+    assert!((cap_area(&clipped) - expected_area).abs() < 0.05 * expected_area);
+}
+
+#[test]
+fn test_clip_with_waves(){
+    use crate::geometry::waves::*;
+
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+    let waves = AiryWaves{
+        components: vec![WaveComponent{amplitude: 0.3, wavelength: 8.0, direction: Vec2::X, phase: 0.0}],
+        ..default()
+    };
+
+    let clipped = hull.clip_with_surface(&waves);
+
+    //the crest sits over the middle of the cube so more than half of it is under water
+    let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&clipped, Vec3::new(1.0, -3.0, 2.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-3, "Clipped cube is not closed.");
+    assert!(origin_volume > 4.0 && origin_volume < 4.0 + 4.0 * 0.3);
+}
+
+#[test]
+fn test_clip_with_bounds(){
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+
+    let clipped = hull.clip_with_plane(&Plane::default()).clip_with_bounds(&[
+        Plane{normal: Vec3::X, zero_point: Vec3::new(0.5, 0.0, 0.0)},
+        Plane{normal: Vec3::new(0.0, 0.0, -1.0), zero_point: Vec3::new(0.0, 0.0, -0.5)}
+    ]);
+
+    //1.5 wide, 1 deep and 1.5 long
+    let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&clipped, Vec3::new(2.0, -3.0, 1.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-4, "Bounded cube is not closed.");
+    assert!((origin_volume - 2.25).abs() < 1e-4, "{}", origin_volume);
+    assert!((cap_area(&clipped) - 2.25).abs() < 1e-4);
+
+    //the cap's outline follows the bounds too, counter clockwise around the normal
+    assert_eq!(clipped.cap_loops.len(), 1);
+    let outline: Vec<Vec3> = clipped.cap_loops[0].iter().map(|index| clipped.position(*index)).collect();
+    assert!(outline.iter().all(|point| point.x <= 0.5 + 1e-5 && point.z >= -0.5 - 1e-5));
+    let mut outline_area = 0.0;
+    for (i, point) in outline.iter().enumerate(){
+        outline_area += point.cross(outline[(i + 1) % outline.len()]).dot(clipped.plane.normal) / 2.0;
+    }
+    assert!((outline_area - 2.25).abs() < 1e-4, "{}", outline_area);
+}
+
+#[test]
+fn test_clip_with_convex(){
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+
+    //a wedge cutting off one corner of the cube, then sliced again through the first cut
+    let clipped = hull.clip_with_convex(&[
+        Plane{normal: Vec3::new(1.0, 1.0, 0.0).normalize(), zero_point: Vec3::ZERO},
+        Plane{normal: Vec3::Z, zero_point: Vec3::new(0.0, 0.0, 0.5)},
+        Plane{normal: Vec3::new(0.0, 1.0, 1.0).normalize(), zero_point: Vec3::new(0.0, 0.2, 0.0)}
+    ]);
+
+    let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&clipped, Vec3::new(-2.0, 4.0, 1.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-4, "Convex clip is not closed.");
+    assert!(clipped.cap_indices.is_empty());
+
+    //patch vertices of earlier passes were carried over and cut again, every index still resolves
+    for index in clipped.indices.iter().chain(clipped.wall_indices.iter()){
+        if let ClippedIndex::PatchIndex(index) = index{
+            assert!(*index < clipped.patch_vertices.len());
+        }
+    }
+    assert!(clipped.wall_indices.iter().any(|index| matches!(index, ClippedIndex::PatchIndex(_))));
+
+    //with a single plane it is the same piece as clipping with that plane
+    let plane = Plane{normal: Vec3::new(0.2, 1.0, -0.3).normalize(), zero_point: Vec3::new(0.0, 0.1, 0.0)};
+    let convex_volume = hull.clip_with_convex(&[plane]).volume();
+    assert!((convex_volume - hull.clip_with_plane(&plane).volume()).abs() < 1e-4);
+}
+
+#[test]
+fn test_split(){
+    let hull = Hull::try_from(Mesh::from(shape::Torus::default())).unwrap();
+    let plane = Plane{normal: Vec3::new(0.3, 1.0, 0.1).normalize(), zero_point: Vec3::new(0.0, 0.1, 0.0)};
+
+    let split = hull.split_with_plane(&plane);
+
+    //the emerged part is closed by the same cap turned over
+    let origin_volume = closed_volume_about(&split.emerged, Vec3::ZERO);
+    let offset_volume = closed_volume_about(&split.emerged, Vec3::new(1.0, 3.0, -2.0));
+    assert!((origin_volume - offset_volume).abs() < 1e-3, "Emerged part is not closed.");
+    assert_eq!(split.emerged.cap_indices.len(), split.submerged.cap_indices.len());
+    assert!((cap_area(&split.emerged) - cap_area(&split.submerged)).abs() < 1e-4);
+
+    let whole = hull.clip_with_plane(&Plane{normal: Vec3::Y, zero_point: Vec3::Y * 100.0}).volume();
+    assert!((split.submerged.volume() + split.emerged.volume() - whole).abs() < 1e-3 * whole);
+    assert!((split.submerged.volume() - hull.clip_with_plane(&plane).volume()).abs() < 1e-5);
+}
+
+#[test]
+fn test_vertices_on_plane(){
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+
+    //top face lying in the plane, the whole cube is under and the top closes it without a cap
+    let clipped = hull.clip_with_plane(&Plane{normal: Vec3::Y, zero_point: Vec3::Y});
+    assert!((clipped.volume() - 8.0).abs() < 1e-4);
+    assert!(clipped.cap_indices.is_empty());
+
+    //bottom face lying in the plane, nothing is under
+    let clipped = hull.clip_with_plane(&Plane{normal: Vec3::Y, zero_point: Vec3::NEG_Y});
+    assert!(clipped.volume().abs() < 1e-6);
+
+    //diagonal cut running through two edges and four corners
+    let split = hull.split_with_plane(&Plane{normal: Vec3::new(1.0, -1.0, 0.0).normalize(), zero_point: Vec3::ZERO});
+    for part in [&split.submerged, &split.emerged]{
+        let origin_volume = closed_volume_about(part, Vec3::ZERO);
+        let offset_volume = closed_volume_about(part, Vec3::new(2.0, 1.0, -3.0));
+        assert!((origin_volume - offset_volume).abs() < 1e-4, "Diagonal cut is not closed.");
+        assert!((origin_volume - 4.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_clip_through_every_vertex(){
+    let initial_mesh: Mesh = shape::Torus{subdivisions_segments: 12, subdivisions_sides: 8, ..default()}.into();
+    let hull = Hull::try_from(initial_mesh).unwrap();
+
+    //cut right through each ring of vertices, and just beside it
+    let heights: Vec<f32> = hull.shape.vertices.iter().map(|vertex| vertex.position.y).collect();
+    for height in heights{
+        for offset in [0.0, 1e-7, -1e-7]{
+            let clipped = hull.clip_with_plane(&Plane{normal: Vec3::Y, zero_point: Vec3::Y * (height + offset)});
+            let origin_volume = closed_volume_about(&clipped, Vec3::ZERO);
+            let offset_volume = closed_volume_about(&clipped, Vec3::new(0.0, -2.0, 1.0));
+            assert!((origin_volume - offset_volume).abs() < 1e-3, "Cut at {} is not closed.", height + offset);
+        }
+    }
+}
+
+#[test]
+fn test_tolerance_follows_scale(){
+    //a hull smaller than the absolute tolerance has every vertex on the plane through its middle
+    let size = 1e-5;
+    let hull = Hull::from_mesh(&Mesh::from(shape::Cube::new(size)), size * 1e-3).unwrap();
+    let plane = Plane{normal: Vec3::Y, zero_point: Vec3::ZERO};
+
+    let relative = hull.clip_with_plane(&plane).volume();
+    assert!((relative / size.powi(3) - 0.5).abs() < 1e-3);
+
+    let absolute = hull.clip_with_tolerance(&plane, ClipTolerance::Absolute(1e-5)).volume();
+    assert!((absolute / size.powi(3) - 0.5).abs() > 0.1);
+}
@@ -0,0 +1,47 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use super::Hull;
+
+///Draws the edges of every [`Hull`] carrying a [`HullGizmo`] with gizmo lines, to check the physics hull against the visible mesh.
+///Not part of [`super::HullPlugin`], add it while debugging.
+pub struct HullDebugPlugin;
+
+impl Plugin for HullDebugPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, hull_gizmo_system.after(TransformSystem::TransformPropagate));
+    }
+}
+
+///Marks a hull to be drawn by the [`HullDebugPlugin`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HullGizmo{
+    ///Colour of the regular edges.
+    pub color: Color,
+    ///Colour of boundary and non-manifold edges, which keep the hull from being watertight.
+    pub defect_color: Color
+}
+
+impl Default for HullGizmo{
+    fn default() -> Self {
+        Self { color: Color::GREEN, defect_color: Color::RED }
+    }
+}
+
+fn hull_gizmo_system(
+    mut gizmos: Gizmos,
+    hull_query: Query<(&Hull, &GlobalTransform, &HullGizmo)>
+){
+    for (hull, transform, gizmo) in hull_query.iter(){
+        let shape = hull.shape();
+        let world_from_local = transform.affine();
+        for edge in 0..shape.edge_count(){
+            let [start, end] = shape.edge_vertices(edge).map(|index| world_from_local.transform_point3(shape.vertices[index].position));
+            let color = if shape.is_boundary_edge(edge) || shape.is_non_manifold_edge(edge){
+                gizmo.defect_color
+            }else{
+                gizmo.color
+            };
+            gizmos.line(start, end, color);
+        }
+    }
+}
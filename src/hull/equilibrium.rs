@@ -0,0 +1,231 @@
+use bevy::utils::thiserror::Error;
+
+use crate::geometry::Plane;
+
+use super::clipping::ClipTolerance;
+
+use super::*;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EquilibriumError{
+    #[error("Hull cannot displace enough fluid to carry the mass.")]
+    Sinks,
+    #[error("Solver did not settle on an equilibrium.")]
+    DidNotConverge
+}
+
+///Loading case for [`Hull::equilibrium`], directions and the centre of gravity are in the hull's local space.
+#[derive(Clone, Copy, Debug)]
+pub struct EquilibriumConfig{
+    pub up: Vec3,
+    ///Bow direction of the hull.
+    pub longitudinal: Vec3,
+    pub mass: f32,
+    pub centre_of_gravity: Vec3,
+    ///Fluid density in kg/m^3.
+    pub density: f32,
+    ///Largest accepted volume error relative to the required volume, and lever error relative to the hull's size.
+    pub tolerance: f32,
+    pub max_iterations: usize,
+    ///Tolerance the hull is clipped with at each trial attitude.
+    pub clip_tolerance: ClipTolerance
+}
+
+impl Default for EquilibriumConfig{
+    fn default() -> Self {
+        Self {
+            up: Vec3::Y,
+            longitudinal: Vec3::X,
+            mass: 0.0,
+            centre_of_gravity: Vec3::ZERO,
+            density: 1000.0,
+            tolerance: 1e-4,
+            max_iterations: 50,
+            clip_tolerance: ClipTolerance::default()
+        }
+    }
+}
+
+///Floating position of a hull, using the draft, heel and trim conventions of [`super::hydrostatics::HydrostaticsConfig`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equilibrium{
+    pub draft: f32,
+    pub heel: f32,
+    pub trim: f32,
+    ///Water surface in the hull's local space.
+    pub plane: Plane,
+    pub centre_of_buoyancy: Vec3,
+    ///Rotation taking the hull from its local space into a world whose surface faces `up`.
+    pub rotation: Quat
+}
+
+impl Equilibrium{
+    ///Transform that puts the hull at rest on a surface at `surface_height` along `up`,
+    ///with its origin above the world origin.
+    pub fn transform(&self, up: Vec3, surface_height: f32) -> Transform{
+        let up = up.normalize();
+        let rotated_surface_point = self.rotation * self.plane.zero_point;
+        return Transform{
+            translation: up * (surface_height - rotated_surface_point.dot(up)),
+            rotation: self.rotation,
+            ..default()
+        };
+    }
+}
+
+struct Balance{
+    plane: Plane,
+    centre_of_buoyancy: Vec3,
+    //volume error, transverse lever and longitudinal lever
+    residual: Vec3
+}
+
+impl Hull{
+    fn balance(&self, config: &EquilibriumConfig, attitude: Vec3) -> Balance{
+        let plane = self.attitude_plane(config.up, config.longitudinal, attitude.x, attitude.y, attitude.z);
+        let (volume, centre_of_buoyancy) = self.clip_with_tolerance(&plane, config.clip_tolerance).volume_and_centroid();
+
+        let normal = plane.normal.normalize();
+        let longitudinal = (config.longitudinal - normal * config.longitudinal.dot(normal)).normalize_or_zero();
+        let starboard = longitudinal.cross(normal);
+        let lever = centre_of_buoyancy - config.centre_of_gravity;
+
+        return Balance{
+            plane,
+            centre_of_buoyancy,
+            residual: Vec3::new(
+                volume - config.mass / config.density,
+                lever.dot(starboard),
+                lever.dot(longitudinal)
+            )
+        };
+    }
+
+    ///Finds the draft, heel and trim where buoyancy carries `config.mass` and lines up with the centre of gravity.
+    ///The draft is bracketed upright first, then heel and trim are solved together with Newton's method,
+    ///so the result is the equilibrium closest to upright, which might be an unstable one for a top heavy hull.
+    pub fn equilibrium(&self, config: &EquilibriumConfig) -> Result<Equilibrium, EquilibriumError>{
+        let required_volume = config.mass / config.density;
+        if required_volume > self.shape.volume(){
+            return Err(EquilibriumError::Sinks);
+        }
+
+        let up = config.up.normalize();
+        let keel = self.keel_height(up);
+        let height = self.shape.vertices.iter()
+            .map(|vertex| vertex.position.dot(up))
+            .fold(f32::NEG_INFINITY, f32::max) - keel;
+        let volume_tolerance = config.tolerance * required_volume.max(f32::EPSILON);
+        let lever_tolerance = config.tolerance * height;
+
+        //upright draft by bisection, the displaced volume only grows with draft
+        let mut low = 0.0;
+        let mut high = height;
+        for _ in 0..config.max_iterations{
+            let middle = (low + high) / 2.0;
+            if self.balance(config, Vec3::new(middle, 0.0, 0.0)).residual.x < 0.0{
+                low = middle;
+            }else{
+                high = middle;
+            }
+        }
+
+        //Newton's method over (draft, heel, trim) with a finite difference jacobian
+        let steps = Vec3::new(1e-3 * height, 1e-3, 1e-3);
+        let mut attitude = Vec3::new((low + high) / 2.0, 0.0, 0.0);
+        let mut balance = self.balance(config, attitude);
+        for _ in 0..config.max_iterations{
+            let residual = balance.residual;
+            if residual.x.abs() <= volume_tolerance && residual.y.abs() <= lever_tolerance && residual.z.abs() <= lever_tolerance{
+                return Ok(Equilibrium{
+                    draft: attitude.x,
+                    heel: attitude.y,
+                    trim: attitude.z,
+                    plane: balance.plane,
+                    centre_of_buoyancy: balance.centre_of_buoyancy,
+                    rotation: attitude_rotation(config, attitude)
+                });
+            }
+
+            let mut columns = [Vec3::ZERO; 3];
+            for (i, column) in columns.iter_mut().enumerate(){
+                let mut offset = Vec3::ZERO;
+                offset[i] = steps[i];
+                *column = (self.balance(config, attitude + offset).residual - residual) / steps[i];
+            }
+            let jacobian = Mat3::from_cols(columns[0], columns[1], columns[2]);
+            if jacobian.determinant().abs() <= f32::EPSILON{
+                return Err(EquilibriumError::DidNotConverge);
+            }
+            let mut step = -(jacobian.inverse() * residual);
+
+            //halve the step until the residual improves, keeps the solver from jumping out of the water
+            let current_error = scaled_error(residual, volume_tolerance, lever_tolerance);
+            let mut accepted = false;
+            for _ in 0..10{
+                let candidate = attitude + step;
+                let candidate_balance = self.balance(config, candidate);
+                if scaled_error(candidate_balance.residual, volume_tolerance, lever_tolerance) < current_error{
+                    attitude = candidate;
+                    balance = candidate_balance;
+                    accepted = true;
+                    break;
+                }
+                step /= 2.0;
+            }
+            if !accepted{
+                return Err(EquilibriumError::DidNotConverge);
+            }
+        }
+        return Err(EquilibriumError::DidNotConverge);
+    }
+}
+
+fn scaled_error(residual: Vec3, volume_tolerance: f32, lever_tolerance: f32) -> f32{
+    (residual / Vec3::new(volume_tolerance, lever_tolerance, lever_tolerance)).length_squared()
+}
+
+fn attitude_rotation(config: &EquilibriumConfig, attitude: Vec3) -> Quat{
+    let up = config.up.normalize();
+    let longitudinal = (config.longitudinal - up * config.longitudinal.dot(up)).normalize();
+    let starboard = longitudinal.cross(up);
+    return Quat::from_axis_angle(starboard, -attitude.z) * Quat::from_axis_angle(longitudinal, attitude.y);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_box_equilibrium(){
+        //4 long, 2 high and 2 wide with the keel at y = -1
+        let hull = Hull::try_from(Mesh::from(shape::Box::new(4.0, 2.0, 2.0))).unwrap();
+
+        let level = hull.equilibrium(&EquilibriumConfig{
+            mass: 4000.0,
+            centre_of_gravity: Vec3::new(0.0, -0.5, 0.0),
+            ..default()
+        }).unwrap();
+        assert!((level.draft - 0.5).abs() < 1e-3);
+        assert!(level.heel.abs() < 1e-3);
+        assert!(level.trim.abs() < 1e-3);
+
+        //weight moved to the bow has to trim the bow down
+        let config = EquilibriumConfig{
+            mass: 4000.0,
+            centre_of_gravity: Vec3::new(0.3, -0.5, 0.0),
+            ..default()
+        };
+        let trimmed = hull.equilibrium(&config).unwrap();
+        assert!(trimmed.trim > 0.0);
+        let (volume, _) = hull.clip_with_plane(&trimmed.plane).volume_and_centroid();
+        assert!((volume - 4.0).abs() < 1e-2);
+
+        //resting on a surface at y = 0 leaves the local water plane at world height 0
+        let transform = trimmed.transform(Vec3::Y, 0.0);
+        assert!(transform.transform_point(trimmed.plane.zero_point).y.abs() < 1e-4);
+        assert!((transform.rotation * trimmed.plane.normal - Vec3::Y).length() < 1e-3);
+
+        assert_eq!(hull.equilibrium(&EquilibriumConfig{mass: 20000.0, ..default()}), Err(EquilibriumError::Sinks));
+    }
+}
@@ -1,6 +1,13 @@
 use bevy::prelude::*;
 
 pub mod into;
+pub mod topology;
+pub mod validation;
+pub mod repair;
+pub mod decimate;
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Default)]
 pub(super) struct HullVertex{
@@ -11,14 +18,28 @@ pub(super) struct HullVertex{
 
 #[derive(Default)]
 pub(super) struct HullEdge{
+    //in the direction the first face runs along it
     pub(super) vertex_indexes: [usize;2],
-    pub(super) face_indexes: [usize;2],
+    //the second face is missing on a boundary edge
+    pub(super) face_indexes: [Option<usize>;2],
+    //faces past the first two, only non-manifold edges have any
+    pub(super) extra_face_indexes: Vec<usize>
+}
+
+//one side of an edge as a face runs along it, half edge `face * 3 + i` runs from corner i to corner i + 1
+#[derive(Default)]
+pub(super) struct HullHalfEdge{
+    pub(super) edge_index: usize,
+    //the neighbouring face's half edge running back the other way, missing on boundaries and where the winding flips
+    pub(super) opposite: Option<usize>
 }
 
 #[derive(Default)]
 pub(super) struct HullFace{
     pub(super) edge_indexes : [usize;3],
-    pub(super) vertex_indices: [usize;3]
+    pub(super) vertex_indices: [usize;3],
+    //corners as indices into the source mesh's vertices, from before welding
+    pub(super) source_indices: [usize;3]
 }
 
 
@@ -27,6 +48,26 @@ pub(super) struct HullFace{
 pub struct HullShape{
     pub(super) vertices: Vec<HullVertex>,
     pub(super) edges: Vec<HullEdge>,
-    pub(super) faces: Vec<HullFace>
+    pub(super) faces: Vec<HullFace>,
+    pub(super) half_edges: Vec<HullHalfEdge>,
+    //hull vertex each vertex of the source mesh was welded into
+    pub(super) source_vertices: Vec<usize>,
+    //source mesh triangles left out because welding merged their corners
+    pub(super) collapsed_faces: Vec<[usize;3]>
+}
+
+impl HullShape{
+    pub fn vertex_count(&self) -> usize{
+        self.vertices.len()
+    }
+
+    pub fn face_count(&self) -> usize{
+        self.faces.len()
+    }
+
+    ///For each vertex of the mesh the shape was built from, the index of the hull vertex it was welded into.
+    pub fn source_vertex_map(&self) -> &[usize]{
+        &self.source_vertices
+    }
 }
 
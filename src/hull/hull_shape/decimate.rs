@@ -0,0 +1,499 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+
+use bevy::math::{DMat3, DMat4, DVec3, DVec4};
+
+use crate::geometry::Plane;
+
+use super::*;
+
+///Settings for [`HullShape::decimate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecimationSettings{
+    ///Stop once the shape is down to this many faces.
+    pub target_faces: usize,
+    ///Place each merged vertex so the enclosed volume does not change.
+    pub preserve_volume: bool,
+    ///Waterline to keep in shape, in the hull's space. Faces within `waterline_band` of it count `waterline_weight` times more,
+    ///and vertices within the band are held at their height above it.
+    pub waterline: Option<Plane>,
+    pub waterline_band: f32,
+    pub waterline_weight: f32,
+    ///How strongly boundary edges are held in place compared to the faces, only open shapes have any.
+    pub boundary_weight: f32
+}
+
+impl DecimationSettings{
+    pub fn new(target_faces: usize) -> Self{
+        Self { target_faces, ..default() }
+    }
+}
+
+impl Default for DecimationSettings{
+    fn default() -> Self {
+        Self {
+            target_faces: 256,
+            preserve_volume: true,
+            waterline: None,
+            waterline_band: 0.1,
+            waterline_weight: 10.0,
+            boundary_weight: 100.0
+        }
+    }
+}
+
+///Sum of squared distances to a set of planes, as x^T a x + 2 b.x + c (Garland and Heckbert).
+#[derive(Clone, Copy, Default)]
+struct Quadric{
+    a: DMat3,
+    b: DVec3,
+    c: f64
+}
+
+impl Quadric{
+    //squared distance to the plane through `point` with unit `normal`, times `weight`
+    fn plane(normal: DVec3, point: DVec3, weight: f64) -> Self{
+        let d = -normal.dot(point);
+        return Self {
+            a: DMat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z) * weight,
+            b: normal * d * weight,
+            c: d * d * weight
+        };
+    }
+
+    fn error(&self, point: DVec3) -> f64{
+        point.dot(self.a * point) + 2.0 * self.b.dot(point) + self.c
+    }
+}
+
+impl std::ops::Add for Quadric{
+    type Output = Quadric;
+
+    fn add(self, other: Quadric) -> Quadric{
+        Quadric { a: self.a + other.a, b: self.b + other.b, c: self.c + other.c }
+    }
+}
+
+impl std::ops::AddAssign for Quadric{
+    fn add_assign(&mut self, other: Quadric){
+        *self = *self + other;
+    }
+}
+
+//Merging `remove` into `keep` at `position`, valid while neither vertex's stamp has moved on
+struct Collapse{
+    cost: f64,
+    keep: usize,
+    remove: usize,
+    position: DVec3,
+    stamps: [u32;2]
+}
+
+impl PartialEq for Collapse{
+    fn eq(&self, other: &Self) -> bool{
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse{}
+
+impl PartialOrd for Collapse{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>{
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse{
+    //reversed so the heap hands out the cheapest collapse first,
+    //ties go to the lowest vertices so symmetric shapes decimate the same way every run
+    fn cmp(&self, other: &Self) -> Ordering{
+        other.cost.total_cmp(&self.cost)
+            .then_with(|| (other.keep, other.remove).cmp(&(self.keep, self.remove)))
+            .then_with(|| self.stamps.cmp(&other.stamps))
+    }
+}
+
+struct Decimator<'a>{
+    settings: &'a DecimationSettings,
+    positions: Vec<DVec3>,
+    quadrics: Vec<Quadric>,
+    triangles: Vec<[usize;3]>,
+    face_alive: Vec<bool>,
+    face_count: usize,
+    vertex_faces: Vec<Vec<usize>>,
+    //vertex each removed vertex was merged into
+    merged_into: Vec<Option<usize>>,
+    //vertices on non-manifold edges, collapsing around them could tear the shape apart
+    locked: Vec<bool>,
+    //bumped whenever a vertex's neighbourhood changes, outdating the collapses queued for it
+    stamps: Vec<u32>,
+    heap: BinaryHeap<Collapse>
+}
+
+impl<'a> Decimator<'a>{
+    fn new(shape: &HullShape, settings: &'a DecimationSettings) -> Self{
+        let vertex_count = shape.vertices.len();
+        let mut decimator = Self {
+            settings,
+            positions: shape.vertices.iter().map(|vertex| vertex.position.as_dvec3()).collect(),
+            quadrics: vec![Quadric::default(); vertex_count],
+            triangles: shape.faces.iter().map(|face| face.vertex_indices).collect(),
+            face_alive: vec![true; shape.faces.len()],
+            face_count: shape.faces.len(),
+            vertex_faces: shape.vertices.iter().map(|vertex| vertex.face_indices.clone()).collect(),
+            merged_into: vec![None; vertex_count],
+            locked: vec![false; vertex_count],
+            stamps: vec![0; vertex_count],
+            heap: BinaryHeap::new()
+        };
+
+        let waterline = settings.waterline.map(|plane| (plane, plane.normal.normalize().as_dvec3()));
+        let near_waterline = |point: DVec3| waterline.is_some_and(|(plane, _)| plane.distance_from_plane(point.as_vec3()).abs() <= settings.waterline_band);
+
+        //every face's plane, weighted by its area so dense patches do not outweigh large faces
+        let mut vertex_areas = vec![0.0; vertex_count];
+        for triangle in decimator.triangles.iter(){
+            let [a, b, c] = triangle.map(|index| decimator.positions[index]);
+            let normal = (b - a).cross(c - a);
+            let area = normal.length() / 2.0;
+            if area <= 0.0{
+                continue;
+            }
+            //faces touching the waterline band or crossing it
+            let heights = waterline.map(|(plane, _)| [a, b, c].map(|point| plane.distance_from_plane(point.as_vec3())));
+            let on_waterline = heights.is_some_and(|heights| {
+                heights.iter().any(|height| height.abs() <= settings.waterline_band)
+                || (heights.iter().any(|height| *height < 0.0) && heights.iter().any(|height| *height > 0.0))
+            });
+            let weight = if on_waterline {area * settings.waterline_weight as f64} else {area};
+            let quadric = Quadric::plane(normal / (2.0 * area), a, weight);
+            for index in triangle{
+                decimator.quadrics[*index] += quadric;
+                vertex_areas[*index] += area / 3.0;
+            }
+        }
+
+        //hold the vertices near the waterline at their height
+        if let Some((_, up)) = waterline{
+            for (index, position) in decimator.positions.iter().enumerate(){
+                if near_waterline(*position){
+                    decimator.quadrics[index] += Quadric::plane(up, *position, vertex_areas[index] * settings.waterline_weight as f64);
+                }
+            }
+        }
+
+        for edge in 0..shape.edges.len(){
+            let [start, end] = shape.edge_vertices(edge);
+            if shape.is_non_manifold_edge(edge){
+                decimator.locked[start] = true;
+                decimator.locked[end] = true;
+            }else if shape.is_boundary_edge(edge){
+                //a plane standing on the edge, square to its face, keeps the boundary from drifting
+                let Some(face) = shape.edge_faces(edge).next() else {continue};
+                let [a, b, c] = decimator.triangles[face].map(|index| decimator.positions[index]);
+                let direction = decimator.positions[end] - decimator.positions[start];
+                let normal = direction.cross((b - a).cross(c - a)).normalize_or_zero();
+                let quadric = Quadric::plane(normal, decimator.positions[start], direction.length_squared() * settings.boundary_weight as f64);
+                decimator.quadrics[start] += quadric;
+                decimator.quadrics[end] += quadric;
+            }
+        }
+
+        for edge in 0..shape.edges.len(){
+            let [start, end] = shape.edge_vertices(edge);
+            decimator.queue(start, end);
+        }
+        return decimator;
+    }
+
+    fn neighbours(&self, vertex: usize) -> BTreeSet<usize>{
+        let mut neighbours = BTreeSet::new();
+        for face in self.vertex_faces[vertex].iter(){
+            neighbours.extend(self.triangles[*face].iter().filter(|other| **other != vertex));
+        }
+        return neighbours;
+    }
+
+    //Linear constraint g.x = h on the merged vertex keeping the volume around the edge unchanged (Lindstrom and Turk)
+    fn volume_constraint(&self, keep: usize, remove: usize) -> (DVec3, f64){
+        //volumes are measured from the edge's midpoint to keep the numbers small
+        let origin = (self.positions[keep] + self.positions[remove]) / 2.0;
+        let mut gradient = DVec3::ZERO;
+        let mut volume = 0.0;
+        let faces = self.vertex_faces[keep].iter().chain(self.vertex_faces[remove].iter().filter(|face| !self.triangles[**face].contains(&keep)));
+        for face in faces{
+            let triangle = self.triangles[*face];
+            let [a, b, c] = triangle.map(|index| self.positions[index] - origin);
+            volume += a.dot(b.cross(c)) / 6.0;
+            if triangle.contains(&keep) && triangle.contains(&remove){
+                continue;
+            }
+            //the face's volume with the moved corner at x is x.(b x c) / 6
+            let corner = triangle.iter().position(|index| *index == keep || *index == remove).unwrap();
+            let [b, c] = [triangle[(corner + 1) % 3], triangle[(corner + 2) % 3]].map(|index| self.positions[index] - origin);
+            gradient += b.cross(c) / 6.0;
+        }
+        return (gradient, volume + gradient.dot(origin));
+    }
+
+    fn queue(&mut self, first: usize, second: usize){
+        if self.locked[first] || self.locked[second] || first == second{
+            return;
+        }
+        let (keep, remove) = (first.min(second), first.max(second));
+        let quadric = self.quadrics[keep] + self.quadrics[remove];
+        let start = self.positions[keep];
+        let end = self.positions[remove];
+        let midpoint = (start + end) / 2.0;
+        //solutions further out than this come from nearly singular systems
+        let reach = (end - start).length() * 2.0;
+        let within_reach = |point: DVec3| point.is_finite() && point.distance(midpoint) <= reach;
+
+        let constraint = if self.settings.preserve_volume{
+            Some(self.volume_constraint(keep, remove)).filter(|(gradient, _)| gradient.length_squared() > 0.0)
+        }else{
+            None
+        };
+
+        let optimal = match constraint{
+            Some((gradient, target)) => {
+                //minimise the quadric on the constraint plane with a Lagrange multiplier
+                let a = quadric.a * 2.0;
+                let system = DMat4::from_cols(
+                    a.x_axis.extend(gradient.x),
+                    a.y_axis.extend(gradient.y),
+                    a.z_axis.extend(gradient.z),
+                    gradient.extend(0.0)
+                );
+                let solution = system.inverse() * DVec4::from((quadric.b * -2.0, target));
+                Some(solution.truncate())
+            },
+            None => Some(quadric.a.inverse() * -quadric.b)
+        }.filter(|point| within_reach(*point));
+
+        let position = optimal.unwrap_or_else(|| {
+            //fall back on the best of the edge's ends and middle, moved onto the constraint
+            [start, end, midpoint].map(|point| match constraint{
+                Some((gradient, target)) => point + gradient * (target - gradient.dot(point)) / gradient.length_squared(),
+                None => point
+            }).into_iter().min_by(|a, b| quadric.error(*a).total_cmp(&quadric.error(*b))).unwrap()
+        });
+
+        self.heap.push(Collapse {
+            cost: quadric.error(position).max(0.0),
+            keep,
+            remove,
+            position,
+            stamps: [self.stamps[keep], self.stamps[remove]]
+        });
+    }
+
+    //Whether merging keeps the shape manifold, keeps faces from turning over and leaves no doubled faces
+    fn can_collapse(&self, collapse: &Collapse) -> bool{
+        let (keep, remove) = (collapse.keep, collapse.remove);
+        let shared: Vec<usize> = self.vertex_faces[keep].iter().copied().filter(|face| self.triangles[*face].contains(&remove)).collect();
+        if shared.is_empty(){
+            return false;
+        }
+
+        //the only vertices both ends may share are the far corners of the faces between them
+        let opposite: BTreeSet<usize> = shared.iter().flat_map(|face| self.triangles[*face]).filter(|index| *index != keep && *index != remove).collect();
+        let mut common = self.neighbours(keep);
+        common.retain(|index| *index != remove);
+        let remove_neighbours = self.neighbours(remove);
+        common.retain(|index| remove_neighbours.contains(index));
+        if common != opposite{
+            return false;
+        }
+
+        let mut merged_faces = HashSet::new();
+        for face in self.vertex_faces[keep].iter().chain(self.vertex_faces[remove].iter()){
+            let triangle = self.triangles[*face];
+            if triangle.contains(&keep) && triangle.contains(&remove){
+                continue;
+            }
+            let [a, b, c] = triangle.map(|index| self.positions[index]);
+            let old_normal = (b - a).cross(c - a);
+            let [a, b, c] = triangle.map(|index| if index == keep || index == remove {collapse.position} else {self.positions[index]});
+            let new_normal = (b - a).cross(c - a);
+            if new_normal.dot(old_normal) <= 0.0 || new_normal.length_squared() <= f64::EPSILON * old_normal.length_squared(){
+                return false;
+            }
+
+            let mut vertices = triangle.map(|index| if index == remove {keep} else {index});
+            vertices.sort();
+            if !merged_faces.insert(vertices){
+                return false;
+            }
+        }
+        return true;
+    }
+
+    fn collapse(&mut self, collapse: Collapse){
+        let (keep, remove) = (collapse.keep, collapse.remove);
+        for face in std::mem::take(&mut self.vertex_faces[remove]){
+            let triangle = &mut self.triangles[face];
+            if triangle.contains(&keep){
+                self.face_alive[face] = false;
+                self.face_count -= 1;
+                for index in *triangle{
+                    self.vertex_faces[index].retain(|other| *other != face);
+                }
+            }else{
+                for index in triangle.iter_mut(){
+                    if *index == remove{
+                        *index = keep;
+                    }
+                }
+                self.vertex_faces[keep].push(face);
+            }
+        }
+        self.positions[keep] = collapse.position;
+        self.quadrics[keep] = self.quadrics[keep] + self.quadrics[remove];
+        self.merged_into[remove] = Some(keep);
+
+        //the moved vertex and its ring have new faces around them, queue their edges again
+        let mut touched = self.neighbours(keep);
+        touched.insert(keep);
+        for vertex in touched.iter(){
+            self.stamps[*vertex] += 1;
+        }
+        let mut edges = BTreeSet::new();
+        for vertex in touched{
+            for neighbour in self.neighbours(vertex){
+                edges.insert((vertex.min(neighbour), vertex.max(neighbour)));
+            }
+        }
+        for (first, second) in edges{
+            self.queue(first, second);
+        }
+    }
+
+    fn run(&mut self){
+        while self.face_count > self.settings.target_faces{
+            let Some(collapse) = self.heap.pop() else {break};
+            let current = self.merged_into[collapse.keep].is_none() && self.merged_into[collapse.remove].is_none()
+                && collapse.stamps == [self.stamps[collapse.keep], self.stamps[collapse.remove]];
+            if current && self.can_collapse(&collapse){
+                self.collapse(collapse);
+            }
+        }
+    }
+}
+
+impl HullShape{
+    ///Simplifies the shape down to `settings.target_faces` faces by collapsing edges, cheapest first by the quadric error metric.
+    ///Collapses that would turn faces over or make the shape non-manifold are skipped, so a shape may stop short of the target.
+    ///Returns the number of faces removed.
+    pub fn decimate(&mut self, settings: &DecimationSettings) -> usize{
+        let mut decimator = Decimator::new(self, settings);
+        decimator.run();
+
+        //compact the surviving vertices and faces
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        let mut vertices = Vec::new();
+        for (index, position) in decimator.positions.iter().enumerate(){
+            if decimator.merged_into[index].is_none(){
+                new_indices[index] = vertices.len();
+                vertices.push(HullVertex{position: position.as_vec3(), ..default()});
+            }
+        }
+
+        let resolve = |mut index: usize| {
+            while let Some(next) = decimator.merged_into[index]{
+                index = next;
+            }
+            return new_indices[index];
+        };
+        self.source_vertices = self.source_vertices.iter().map(|index| resolve(*index)).collect();
+
+        let removed = self.faces.len() - decimator.face_count;
+        let old_faces = std::mem::take(&mut self.faces);
+        for (index, face) in old_faces.into_iter().enumerate(){
+            if decimator.face_alive[index]{
+                let vertex_indices = decimator.triangles[index].map(|vertex| new_indices[vertex]);
+                self.faces.push(HullFace{vertex_indices, source_indices: face.source_indices, ..default()});
+            }
+        }
+        self.vertices = vertices;
+        self.build_topology();
+        return removed;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::hull::Hull;
+
+    use super::*;
+
+    fn sphere() -> HullShape{
+        Mesh::from(shape::UVSphere{radius: 1.0, sectors: 32, stacks: 16}).try_into().unwrap()
+    }
+
+    //off the sphere's rings, so keeping the waterline means keeping the faces that cross it
+    const WATERLINE: Plane = Plane{normal: Vec3::Y, zero_point: Vec3::new(0.0, 0.3, 0.0)};
+
+    fn waterplane_area(shape: HullShape) -> f32{
+        let clipped = Hull::from(shape).clip_with_plane(&WATERLINE);
+        return clipped.waterplane(Vec3::X).area;
+    }
+
+    #[test]
+    fn test_decimate_sphere(){
+        let mut shape = sphere();
+        let volume = shape.volume();
+        let faces = shape.face_count();
+
+        let removed = shape.decimate(&DecimationSettings::new(faces / 4));
+        assert_eq!(shape.face_count(), faces - removed);
+        assert!(shape.face_count() <= faces / 4);
+        assert!(shape.validate().is_empty());
+        assert!((shape.volume() - volume).abs() < volume * 1e-3);
+
+        //the source mesh can still be rebuilt from the simplified shape
+        assert!(shape.source_vertex_map().iter().all(|index| *index < shape.vertex_count()));
+        let _: Mesh = shape.into();
+    }
+
+    #[test]
+    fn test_decimate_is_repeatable(){
+        //the sphere is full of equally cheap collapses, they have to be taken in the same order every time
+        let decimated = || {
+            let mut shape = sphere();
+            shape.decimate(&DecimationSettings::new(shape.face_count() / 4));
+            let positions: Vec<Vec3> = shape.vertices.iter().map(|vertex| vertex.position).collect();
+            let faces: Vec<[usize;3]> = shape.faces.iter().map(|face| face.vertex_indices).collect();
+            (positions, faces)
+        };
+        let first = decimated();
+        for _ in 0..3{
+            assert_eq!(decimated(), first);
+        }
+    }
+
+    #[test]
+    fn test_decimate_stops_at_a_closed_shape(){
+        let mut shape: HullShape = Mesh::from(shape::Cube::new(1.0)).try_into().unwrap();
+        shape.decimate(&DecimationSettings::new(0));
+        assert!(shape.face_count() >= 4);
+        assert!(shape.validate().is_empty());
+        assert!((shape.volume() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_waterline_weight(){
+        let exact = waterplane_area(sphere());
+        let target = sphere().face_count() / 8;
+
+        let mut plain = sphere();
+        plain.decimate(&DecimationSettings::new(target));
+        let mut weighted = sphere();
+        weighted.decimate(&DecimationSettings{waterline: Some(WATERLINE), ..DecimationSettings::new(target)});
+
+        let plain_error = (waterplane_area(plain) - exact).abs();
+        let weighted_error = (waterplane_area(weighted) - exact).abs();
+        assert!(weighted_error < plain_error);
+        assert!(weighted_error < exact * 0.005);
+    }
+}
@@ -1,44 +1,36 @@
 use bevy::{prelude::*, render::{render_resource::PrimitiveTopology, mesh::Indices}};
-use itertools::Itertools;
 use bevy::utils::thiserror::Error;
 use crate::hull::{ClippedHull, clipping::ClippedIndex};
+use crate::geometry::weld::PointWelder;
 
 use super::*;
 
-impl HullVertex {
-    fn add_face(&mut self, face_index: usize){
-        for other_face_vertex in self.face_indices.iter(){
-            if face_index == *other_face_vertex{
-                return;
-            }
-        }
-
-        self.face_indices.push(face_index);
-    }
-}
-
 impl Into<Mesh> for HullShape{
     fn into(self) -> bevy::prelude::Mesh {
+        //rebuilds the source mesh's layout, so duplicated vertices come back out and attributes still line up
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         
         //get positions as a vector
         let mut positions = Vec::new();
 
-        for vertex in self.vertices.iter(){
-            positions.push([vertex.position.x, vertex.position.y, vertex.position.z]);
-        }
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-
-        //build face indexes
         let mut indices = Vec::new();
-        for face in self.faces.iter(){
-            for edge_index in face.edge_indexes.iter(){
-                let edge = self.edges.get(*edge_index).unwrap();
-                indices.push(edge.vertex_indexes[0] as u32);
+        if self.source_vertices.is_empty(){
+            for vertex in self.vertices.iter(){
+                positions.push([vertex.position.x, vertex.position.y, vertex.position.z]);
+            }
+            for face in self.faces.iter(){
+                indices.extend(face.vertex_indices.map(|index| index as u32));
+            }
+        }else{
+            for vertex_index in self.source_vertices.iter(){
+                let position = self.vertices[*vertex_index].position;
+                positions.push([position.x, position.y, position.z]);
+            }
+            for face in self.faces.iter(){
+                indices.extend(face.source_indices.map(|index| index as u32));
             }
-
         }
-
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
         mesh.set_indices(Some(Indices::U32(indices)));
 
 
@@ -48,10 +40,57 @@ impl Into<Mesh> for HullShape{
 
 #[derive(Error, Debug, PartialEq)]
 pub enum HullShapeIntoError{
-    #[error("Can only be used on triangle mesh topologies.")]
+    #[error("Can only be used on triangle list and triangle strip topologies.")]
     WrongTopologyType,
     #[error("Mesh must contain position attribute.")]
-    MissingPositionAttribute
+    MissingPositionAttribute,
+    #[error("Positions must be stored as three 32 bit floats.")]
+    UnsupportedPositionFormat,
+    #[error("Mesh indices point past its vertices.")]
+    IndexOutOfBounds{index: usize, vertex_count: usize},
+    #[error("Mesh is not a watertight hull, see the defects for where.")]
+    Defects(super::validation::HullDefects)
+}
+
+///Source vertices closer than this are merged into one hull vertex, used by the `TryInto` conversion.
+pub const DEFAULT_WELD_TOLERANCE: f32 = 1e-5;
+
+//Corners of every triangle in the mesh as indices into its vertices
+fn source_triangles(mesh: &Mesh, vertex_count: usize) -> Result<Vec<[usize;3]>, HullShapeIntoError>{
+    //meshes without indices use their vertices in order
+    let (indices, restart_index): (Vec<usize>, Option<usize>) = match mesh.indices(){
+        Some(Indices::U16(indices)) => (indices.iter().map(|index| *index as usize).collect(), Some(u16::MAX as usize)),
+        Some(Indices::U32(indices)) => (indices.iter().map(|index| *index as usize).collect(), Some(u32::MAX as usize)),
+        None => ((0..vertex_count).collect(), None)
+    };
+
+    let mut triangles = Vec::with_capacity(indices.len() / 3);
+    match mesh.primitive_topology(){
+        PrimitiveTopology::TriangleList => {
+            for triangle in indices.chunks_exact(3){
+                triangles.push([triangle[0], triangle[1], triangle[2]]);
+            }
+        },
+        PrimitiveTopology::TriangleStrip => {
+            //the maximum index restarts the strip
+            for strip in indices.split(|index| Some(*index) == restart_index){
+                for (i, triangle) in strip.windows(3).enumerate(){
+                    //every other triangle of a strip is wound the other way round
+                    let triangle = if i % 2 == 0 {[triangle[0], triangle[1], triangle[2]]} else {[triangle[1], triangle[0], triangle[2]]};
+                    //strips join up with zero area triangles, they are not part of the surface
+                    if triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[2] != triangle[0]{
+                        triangles.push(triangle);
+                    }
+                }
+            }
+        },
+        _ => return Err(HullShapeIntoError::WrongTopologyType)
+    }
+
+    if let Some(index) = triangles.iter().flatten().find(|index| **index >= vertex_count){
+        return Err(HullShapeIntoError::IndexOutOfBounds{index: *index, vertex_count});
+    }
+    return Ok(triangles);
 }
 
 // Conversion from mesh into HullShape
@@ -59,89 +98,72 @@ impl TryInto<HullShape> for Mesh{
     type Error = HullShapeIntoError;
 
     fn try_into(self) -> Result<HullShape, Self::Error>{
+        HullShape::from_mesh(&self, DEFAULT_WELD_TOLERANCE)
+    }
+}
+
+impl HullShape{
+    ///Builds the shape from a triangle list or strip, indexed or not, merging vertices within `weld_tolerance` of each other.
+    ///Meshes duplicate positions for normals and UVs, without welding their faces would not connect.
+    pub fn from_mesh(mesh: &Mesh, weld_tolerance: f32) -> Result<HullShape, HullShapeIntoError>{
         // Sanity Checks
-        if self.primitive_topology() != PrimitiveTopology::TriangleList{
-            return Err(HullShapeIntoError::MissingPositionAttribute);
-        }else if !self.contains_attribute(Mesh::ATTRIBUTE_POSITION){
-            return Err(HullShapeIntoError::MissingPositionAttribute);
+        if !matches!(mesh.primitive_topology(), PrimitiveTopology::TriangleList | PrimitiveTopology::TriangleStrip){
+            return Err(HullShapeIntoError::WrongTopologyType);
         }
 
         //----process vertices
         //get vertices positions
-        let vertices = self.attribute(Mesh::ATTRIBUTE_POSITION).expect("Mesh does not have position attribute")
-        .as_float3().expect("Vertex attribute format error.");
+        let vertices = mesh.attribute(Mesh::ATTRIBUTE_POSITION).ok_or(HullShapeIntoError::MissingPositionAttribute)?
+        .as_float3().ok_or(HullShapeIntoError::UnsupportedPositionFormat)?;
 
         //get faces
-        let faces = self.indices().expect("Does not contain faces").iter().chunks(3);
-
+        let source_faces = source_triangles(mesh, vertices.len())?;
         let mut output = HullShape::default();
 
-        //import vertices
+        //import vertices, welding the ones sharing a position
+        let mut welder = PointWelder::<usize>::new(weld_tolerance);
         for vertex in vertices{
-            output.vertices.push(
-                HullVertex{
-                    position: Vec3::from_array(*vertex),
-                    ..default()
-                }
-            );
+            let position = Vec3::from_array(*vertex);
+            let vertex_index = welder.weld(position, output.vertices.len());
+            if vertex_index == output.vertices.len(){
+                output.vertices.push(
+                    HullVertex{
+                        position,
+                        ..default()
+                    }
+                );
+            }
+            output.source_vertices.push(vertex_index);
         }
 
         //----face processing
-        for face in &faces{
-            //first, extract the vertex indices from the face chunk
-            let mut face_vertices:[usize;3] = [0,0,0];
-            let mut count:usize = 0;
-            for vertex_index in face{
-                face_vertices[count] = vertex_index;
-                count += 1;
-            }
-            if count != 3{
-                //edge case, probably should not happen
+        for source_indices in source_faces{
+            let face_vertices = source_indices.map(|index| output.source_vertices[index]);
+            if face_vertices[0] == face_vertices[1] || face_vertices[1] == face_vertices[2] || face_vertices[2] == face_vertices[0]{
+                //welding collapsed the face, it is left out but remembered for validation
+                output.collapsed_faces.push(source_indices);
                 continue
             }
 
-            let this_face_index = output.faces.len();
-
-            let mut face_edge_indexes = [0,0,0];
-            //-----edge processing, check if the edge already exists, otherwise create it
-            for i in 0..3{
-                
-
-                let vertex1_index = face_vertices[i];
-                let vertex2_index = face_vertices[(i+1)%3]; //wraps around if at the end
-                let vertex1 = output.vertices.get_mut(vertex1_index).unwrap();
-
-
-                // also add the face to the vertices while iterating
-                vertex1.add_face(this_face_index);
-
-                let mut found_edge = false;
-                //try to find if its an existing edge
-                for edge_index in vertex1.edge_indices.iter(){
-                    let edge = output.edges.get(*edge_index).unwrap();
-                    if edge.face_indexes[0] == vertex2_index || edge.face_indexes[1] == vertex2_index{
-                        found_edge = true;
-                        face_edge_indexes[i] = *edge_index;
-                    }
-                }
-                if !found_edge{
-                    //edge doesn't exist yet, create it
-                    face_edge_indexes[i] = output.edges.len();
-                    output.edges.push(
-                        HullEdge { vertex_indexes: [vertex1_index, vertex2_index], face_indexes: [this_face_index,0], ..default() }
-                    );
-                }
-
-            }
-
-            //create the new face
             output.faces.push(
-                HullFace { edge_indexes: face_edge_indexes, vertex_indices: face_vertices}
+                HullFace { vertex_indices: face_vertices, source_indices, ..default() }
             );
         } 
 
+        output.build_topology();
         return Ok(output);
     }
+
+    ///Same as [`HullShape::from_mesh`], failing with [`HullShapeIntoError::Defects`] unless the shape is watertight.
+    ///Degenerate and collapsed faces leave no hole behind, they are still accepted and show up in [`HullShape::validate`].
+    pub fn from_mesh_validated(mesh: &Mesh, weld_tolerance: f32) -> Result<HullShape, HullShapeIntoError>{
+        let shape = HullShape::from_mesh(mesh, weld_tolerance)?;
+        let defects = shape.validate();
+        if !defects.is_watertight(){
+            return Err(HullShapeIntoError::Defects(defects));
+        }
+        return Ok(shape);
+    }
 }
 
 impl From <ClippedHull> for Mesh{
@@ -164,7 +186,7 @@ impl From <ClippedHull> for Mesh{
 
         //build face indices and load them into the mesh
         let mut indices = Vec::<u32>::new();
-        for clipped_index in value.indices{
+        for clipped_index in value.indices.into_iter().chain(value.cap_indices).chain(value.wall_indices){
             if let ClippedIndex::OriginalIndex(index) = clipped_index{
                 indices.push(index as u32);
             }else if let ClippedIndex::PatchIndex(index) = clipped_index{
@@ -180,6 +202,8 @@ impl From <ClippedHull> for Mesh{
 
 #[cfg(test)]
 mod tests{
+    use itertools::Itertools;
+
     use super::*;
 
     #[test]
@@ -196,8 +220,80 @@ mod tests{
 
         let new_mesh : Mesh = hull.into();
 
-        assert_eq!(original_mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap(), new_mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap());
+        //the torus repeats its seam, those copies come back as the one welded position
+        let original_positions = original_mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap();
+        let new_positions = new_mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap();
+        assert_eq!(original_positions.len(), new_positions.len());
+        for (original, new) in original_positions.iter().zip(new_positions){
+            assert!(Vec3::from_array(*original).distance(Vec3::from_array(*new)) <= DEFAULT_WELD_TOLERANCE);
+        }
         assert_eq!(original_mesh.indices().unwrap().iter().collect_vec(), new_mesh.indices().unwrap().iter().collect_vec());
     }
+
+    #[test]
+    fn test_index_formats(){
+        let mesh: Mesh = shape::Cube::default().into();
+        let shape: HullShape = mesh.clone().try_into().unwrap();
+
+        //the same faces from 16 bit indices
+        let mut short_mesh = mesh.clone();
+        let short_indices: Vec<u16> = mesh.indices().unwrap().iter().map(|index| index as u16).collect();
+        short_mesh.set_indices(Some(Indices::U16(short_indices)));
+        let short_shape: HullShape = short_mesh.try_into().unwrap();
+        assert!(short_shape.faces.iter().zip(shape.faces.iter()).all(|(a, b)| a.vertex_indices == b.vertex_indices));
+
+        //the same faces with every corner spelled out
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap();
+        let unindexed_positions: Vec<[f32;3]> = mesh.indices().unwrap().iter().map(|index| positions[index]).collect();
+        let mut unindexed_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        unindexed_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, unindexed_positions);
+        let unindexed_shape: HullShape = unindexed_mesh.try_into().unwrap();
+        assert_eq!(unindexed_shape.vertex_count(), 8);
+        assert!(unindexed_shape.is_closed());
+        assert!((unindexed_shape.volume() - shape.volume()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_triangle_strip(){
+        //a strip wrapping around the sides of a square tube, restarted to close the two ends
+        let positions = vec![
+            [0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0],
+            [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0]
+        ];
+        let mut strip = Mesh::new(PrimitiveTopology::TriangleStrip);
+        strip.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        strip.set_indices(Some(Indices::U16(vec![0, 1, 2, 3, 4, 5, 6, 7, 0, 1, u16::MAX, 0, 2, 6, 4, u16::MAX, 1, 7, 3, 5])));
+
+        let shape: HullShape = strip.try_into().unwrap();
+        assert_eq!(shape.face_count(), 12);
+        assert!(shape.is_closed());
+        assert!((shape.volume().abs() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_welding(){
+        //a cube mesh has four copies of each corner, one per face plus normals
+        let mesh: Mesh = shape::Cube::default().into();
+        let hull: HullShape = mesh.clone().try_into().unwrap();
+
+        assert_eq!(hull.vertex_count(), 8);
+        assert_eq!(hull.face_count(), 12);
+        assert_eq!(hull.source_vertex_map().len(), mesh.count_vertices());
+        for face in hull.faces.iter(){
+            for (vertex_index, source_index) in face.vertex_indices.iter().zip(face.source_indices){
+                assert_eq!(hull.source_vertex_map()[source_index], *vertex_index);
+            }
+        }
+
+        //copies a little apart merge within the tolerance, and only exact copies merge without one
+        let mut cylinder: Mesh = shape::Cylinder::default().into();
+        let welded_count = HullShape::from_mesh(&cylinder, DEFAULT_WELD_TOLERANCE).unwrap().vertex_count();
+        assert!(welded_count < cylinder.count_vertices());
+        let jittered: Vec<[f32;3]> = cylinder.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap()
+            .iter().enumerate().map(|(i, position)| [position[0] + (i % 2) as f32 * 2e-6, position[1], position[2]]).collect();
+        cylinder.insert_attribute(Mesh::ATTRIBUTE_POSITION, jittered);
+        assert_eq!(HullShape::from_mesh(&cylinder, DEFAULT_WELD_TOLERANCE).unwrap().vertex_count(), welded_count);
+        assert!(HullShape::from_mesh(&cylinder, 0.0).unwrap().vertex_count() > welded_count);
+    }
 }
 
@@ -0,0 +1,266 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::geometry::{Plane, triangulation};
+use crate::hull::mass_properties::VolumeIntegral;
+
+use super::*;
+
+///What [`HullShape::repair`] changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HullRepairs{
+    ///Faces turned over to match the winding of their neighbours.
+    pub flipped_faces: usize,
+    ///Whole shells turned over because they were inside out.
+    pub flipped_shells: usize,
+    ///Boundary loops closed with new faces.
+    pub filled_holes: usize
+}
+
+impl HullShape{
+    ///Makes the shape as watertight as it can be: the winding is made consistent across each shell,
+    ///holes are capped and shells facing inwards are turned over. Non-manifold edges are left alone.
+    pub fn repair(&mut self) -> HullRepairs{
+        let flipped_faces = self.unify_winding();
+        let filled_holes = self.fill_holes();
+        let flipped_shells = self.orient_shells();
+        return HullRepairs{flipped_faces, flipped_shells, filled_holes};
+    }
+
+    fn flip_face(&mut self, face: usize){
+        let face = &mut self.faces[face];
+        face.vertex_indices.swap(1, 2);
+        face.source_indices.swap(1, 2);
+    }
+
+    //whether a face runs along the edge from `start` to `end`
+    fn face_runs_along(&self, face: usize, start: usize, end: usize) -> bool{
+        let vertices = self.faces[face].vertex_indices;
+        return (0..3).any(|i| vertices[i] == start && vertices[(i+1)%3] == end);
+    }
+
+    //the other face of a manifold edge
+    fn across_edge(&self, edge: usize, face: usize) -> Option<usize>{
+        let edge = &self.edges[edge];
+        if !edge.extra_face_indexes.is_empty(){
+            return None;
+        }
+        return match edge.face_indexes{
+            [Some(first), Some(second)] if first == face => Some(second),
+            [Some(first), Some(second)] if second == face => Some(first),
+            _ => None
+        };
+    }
+
+    //Faces connected across manifold edges
+    fn shells(&self) -> Vec<Vec<usize>>{
+        let mut visited = vec![false; self.faces.len()];
+        let mut shells = Vec::new();
+        for seed in 0..self.faces.len(){
+            if visited[seed]{
+                continue;
+            }
+            visited[seed] = true;
+            let mut shell = vec![seed];
+            let mut queue = VecDeque::from([seed]);
+            while let Some(face) = queue.pop_front(){
+                for edge in self.faces[face].edge_indexes{
+                    if let Some(other) = self.across_edge(edge, face){
+                        if !visited[other]{
+                            visited[other] = true;
+                            shell.push(other);
+                            queue.push_back(other);
+                        }
+                    }
+                }
+            }
+            shells.push(shell);
+        }
+        return shells;
+    }
+
+    //Floods out from a face of each shell, turning over neighbours that run the same way along a shared edge
+    fn unify_winding(&mut self) -> usize{
+        let mut flipped = 0;
+        let mut visited = vec![false; self.faces.len()];
+        for seed in 0..self.faces.len(){
+            if visited[seed]{
+                continue;
+            }
+            visited[seed] = true;
+            let mut queue = VecDeque::from([seed]);
+            while let Some(face) = queue.pop_front(){
+                //flipping only reorders a face's corners, its edges stay the same
+                for edge in self.faces[face].edge_indexes{
+                    let Some(other) = self.across_edge(edge, face) else {continue};
+                    if visited[other]{
+                        continue;
+                    }
+                    let [start, end] = self.edges[edge].vertex_indexes;
+                    if self.face_runs_along(face, start, end) == self.face_runs_along(other, start, end){
+                        self.flip_face(other);
+                        flipped += 1;
+                    }
+                    visited[other] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+        if flipped > 0{
+            self.build_topology();
+        }
+        return flipped;
+    }
+
+    //Closes every boundary loop with a triangulated cap
+    fn fill_holes(&mut self) -> usize{
+        //the cap runs back along each boundary edge
+        let mut next_vertices = HashMap::<usize, Vec<usize>>::new();
+        for half_edge in 0..self.half_edges.len(){
+            if self.is_boundary_edge(self.half_edges[half_edge].edge_index){
+                let [start, end] = self.half_edge_vertices(half_edge);
+                next_vertices.entry(end).or_default().push(start);
+            }
+        }
+
+        let mut starts: Vec<usize> = next_vertices.keys().copied().collect();
+        starts.sort();
+        let mut hole_loops = Vec::<Vec<usize>>::new();
+        for start in starts{
+            while next_vertices.get(&start).map_or(false, |next| !next.is_empty()){
+                let mut hole_loop = vec![start];
+                let mut current = start;
+                let mut closed = false;
+                while let Some(next) = next_vertices.get_mut(&current).and_then(|next| next.pop()){
+                    if next == start{
+                        closed = true;
+                        break;
+                    }
+                    hole_loop.push(next);
+                    current = next;
+                }
+                if closed && hole_loop.len() >= 3{
+                    hole_loops.push(hole_loop);
+                }
+            }
+        }
+
+        //new corners borrow the source vertex of the first mesh vertex welded into them
+        let mut source_of_vertex = vec![0; self.vertices.len()];
+        for (source_index, vertex_index) in self.source_vertices.iter().enumerate().rev(){
+            source_of_vertex[*vertex_index] = source_index;
+        }
+
+        for hole_loop in hole_loops.iter(){
+            //Newell's normal follows the loop's direction even when it is not flat
+            let positions: Vec<Vec3> = hole_loop.iter().map(|index| self.vertices[*index].position).collect();
+            let mut normal = Vec3::ZERO;
+            for i in 0..positions.len(){
+                let (current, next) = (positions[i], positions[(i+1)%positions.len()]);
+                normal += Vec3::new(
+                    (current.y - next.y) * (current.z + next.z),
+                    (current.z - next.z) * (current.x + next.x),
+                    (current.x - next.x) * (current.y + next.y)
+                );
+            }
+            let plane = Plane{normal: normal.normalize_or_zero(), zero_point: positions[0]};
+            if plane.normal == Vec3::ZERO{
+                continue;
+            }
+            let (u, v) = plane.basis();
+            let projected: Vec<Vec2> = positions.iter().map(|position| {
+                let offset = *position - plane.zero_point;
+                Vec2::new(offset.dot(u), offset.dot(v))
+            }).collect();
+
+            for triangle in triangulation::triangulate_loops(&[projected]){
+                let vertex_indices = triangle.map(|(_, corner)| hole_loop[corner]);
+                self.faces.push(HullFace{
+                    vertex_indices,
+                    source_indices: vertex_indices.map(|index| source_of_vertex[index]),
+                    ..default()
+                });
+            }
+        }
+
+        if !hole_loops.is_empty(){
+            self.build_topology();
+        }
+        return hole_loops.len();
+    }
+
+    //Turns over shells enclosing a negative volume
+    fn orient_shells(&mut self) -> usize{
+        let mut flipped = 0;
+        for shell in self.shells(){
+            let apex = shell.iter().map(|face| self.vertices[self.faces[*face].vertex_indices[0]].position).sum::<Vec3>() / shell.len() as f32;
+            let mut integral = VolumeIntegral::new(apex);
+            for face in shell.iter(){
+                let [a, b, c] = self.faces[*face].vertex_indices.map(|index| self.vertices[index].position);
+                integral.add_triangle(a, b, c);
+            }
+            if integral.volume() < 0.0{
+                for face in shell{
+                    self.flip_face(face);
+                }
+                flipped += 1;
+            }
+        }
+        if flipped > 0{
+            self.build_topology();
+        }
+        return flipped;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use bevy::render::mesh::Indices;
+
+    use super::*;
+    use super::super::tests::{mesh_from, tetrahedron_positions};
+
+    #[test]
+    fn test_repair_flipped_and_open(){
+        //a tetrahedron with one face flipped and one missing
+        let mut shape = HullShape::from_mesh(&mesh_from(tetrahedron_positions(), vec![0, 2, 1, 0, 3, 1, 1, 2, 3]), 0.0).unwrap();
+
+        let repairs = shape.repair();
+        assert_eq!(repairs.flipped_faces, 1);
+        assert_eq!(repairs.filled_holes, 1);
+        assert!(shape.validate().is_empty());
+        assert!((shape.volume() - 1.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_repair_inside_out(){
+        //every face of a cube turned over
+        let mut mesh: Mesh = shape::Cube::new(2.0).into();
+        let Some(Indices::U32(indices)) = mesh.indices() else {panic!()};
+        let flipped_indices: Vec<u32> = indices.chunks_exact(3).flat_map(|face| [face[0], face[2], face[1]]).collect();
+        mesh.set_indices(Some(Indices::U32(flipped_indices)));
+        let mut shape: HullShape = mesh.try_into().unwrap();
+        assert!(shape.volume() < 0.0);
+
+        let repairs = shape.repair();
+        assert_eq!(repairs, HullRepairs{flipped_faces: 0, flipped_shells: 1, filled_holes: 0});
+        assert!((shape.volume() - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_repair_open_deck(){
+        //a cylinder without its top
+        let mesh: Mesh = shape::Cylinder::default().into();
+        let full_volume = HullShape::from_mesh(&mesh, 1e-5).unwrap().volume();
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap().to_vec();
+        let indices: Vec<u32> = mesh.indices().unwrap().iter().collect::<Vec<usize>>().chunks_exact(3)
+            .filter(|face| face.iter().any(|index| positions[*index][1] < 0.4))
+            .flatten().map(|index| *index as u32).collect();
+        let mut shape = HullShape::from_mesh(&mesh_from(positions, indices), 1e-5).unwrap();
+        assert!(!shape.validate().is_watertight());
+
+        let repairs = shape.repair();
+        assert_eq!(repairs.filled_holes, 1);
+        assert!(shape.validate().is_watertight());
+        assert!((shape.volume() - full_volume).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,14 @@
+use bevy::{prelude::*, render::{mesh::Indices, render_resource::PrimitiveTopology}};
+
+//fixtures shared by the hull shape tests
+
+pub(super) fn mesh_from(positions: Vec<[f32;3]>, indices: Vec<u32>) -> Mesh{
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    return mesh;
+}
+
+pub(super) fn tetrahedron_positions() -> Vec<[f32;3]>{
+    vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+impl HullShape{
+    //Rebuilds the edges, half edges and vertex adjacency from the faces' vertex indices
+    pub(in crate::hull) fn build_topology(&mut self){
+        self.edges.clear();
+        self.half_edges.clear();
+        for vertex in self.vertices.iter_mut(){
+            vertex.edge_indices.clear();
+            vertex.face_indices.clear();
+        }
+
+        //edges are shared by every face running along them, whichever way round
+        let mut edge_lookup = HashMap::<(usize, usize), usize>::new();
+        let mut edge_half_edges = Vec::<Vec<usize>>::new();
+
+        for (face_index, face) in self.faces.iter_mut().enumerate(){
+            for i in 0..3{
+                let vertex1_index = face.vertex_indices[i];
+                let vertex2_index = face.vertex_indices[(i+1)%3]; //wraps around if at the end
+                let key = (vertex1_index.min(vertex2_index), vertex1_index.max(vertex2_index));
+
+                let edge_index = *edge_lookup.entry(key).or_insert_with(|| {
+                    self.edges.push(HullEdge{vertex_indexes: [vertex1_index, vertex2_index], ..default()});
+                    edge_half_edges.push(Vec::new());
+                    self.vertices[vertex1_index].edge_indices.push(self.edges.len() - 1);
+                    self.vertices[vertex2_index].edge_indices.push(self.edges.len() - 1);
+                    self.edges.len() - 1
+                });
+
+                let edge = &mut self.edges[edge_index];
+                if edge.face_indexes[0].is_none(){
+                    edge.face_indexes[0] = Some(face_index);
+                }else if edge.face_indexes[1].is_none(){
+                    edge.face_indexes[1] = Some(face_index);
+                }else{
+                    edge.extra_face_indexes.push(face_index);
+                }
+
+                face.edge_indexes[i] = edge_index;
+                edge_half_edges[edge_index].push(face_index * 3 + i);
+                self.half_edges.push(HullHalfEdge{edge_index, opposite: None});
+
+                let face_indices = &mut self.vertices[face.vertex_indices[i]].face_indices;
+                if !face_indices.contains(&face_index){
+                    face_indices.push(face_index);
+                }
+            }
+        }
+
+        //pair up the half edges of each edge running opposite ways
+        for half_edges in edge_half_edges.iter(){
+            for (i, half_edge) in half_edges.iter().enumerate(){
+                if self.half_edges[*half_edge].opposite.is_some(){
+                    continue;
+                }
+                let start = self.half_edge_vertices(*half_edge)[0];
+                let opposite = half_edges[i+1..].iter().copied().find(|other| {
+                    self.half_edges[*other].opposite.is_none() && self.half_edge_vertices(*other)[0] != start
+                });
+                if let Some(opposite) = opposite{
+                    self.half_edges[*half_edge].opposite = Some(opposite);
+                    self.half_edges[opposite].opposite = Some(*half_edge);
+                }
+            }
+        }
+    }
+
+    ///Start and end vertex of a half edge, half edge `face * 3 + i` runs from the face's corner i to the next.
+    pub fn half_edge_vertices(&self, half_edge: usize) -> [usize;2]{
+        let face = &self.faces[half_edge / 3];
+        let corner = half_edge % 3;
+        return [face.vertex_indices[corner], face.vertex_indices[(corner + 1) % 3]];
+    }
+
+    ///The neighbouring face's half edge running back along the same edge.
+    pub fn opposite_half_edge(&self, half_edge: usize) -> Option<usize>{
+        self.half_edges[half_edge].opposite
+    }
+
+    ///The edge a half edge runs along.
+    pub fn half_edge_edge(&self, half_edge: usize) -> usize{
+        self.half_edges[half_edge].edge_index
+    }
+
+    ///The next half edge around the same face.
+    pub fn next_half_edge(&self, half_edge: usize) -> usize{
+        half_edge - half_edge % 3 + (half_edge + 1) % 3
+    }
+
+    pub fn edge_count(&self) -> usize{
+        self.edges.len()
+    }
+
+    pub fn edge_vertices(&self, edge: usize) -> [usize;2]{
+        self.edges[edge].vertex_indexes
+    }
+
+    ///Every face along an edge, two on a closed manifold.
+    pub fn edge_faces(&self, edge: usize) -> impl Iterator<Item = usize> + '_{
+        let edge = &self.edges[edge];
+        edge.face_indexes.iter().flatten().chain(edge.extra_face_indexes.iter()).copied()
+    }
+
+    ///Edge with a face on one side only.
+    pub fn is_boundary_edge(&self, edge: usize) -> bool{
+        self.edges[edge].face_indexes[1].is_none()
+    }
+
+    ///Edge shared by more than two faces.
+    pub fn is_non_manifold_edge(&self, edge: usize) -> bool{
+        !self.edges[edge].extra_face_indexes.is_empty()
+    }
+
+    pub fn boundary_edges(&self) -> impl Iterator<Item = usize> + '_{
+        (0..self.edges.len()).filter(|edge| self.is_boundary_edge(*edge))
+    }
+
+    ///Faces across each of a face's edges, missing where there is no neighbour running the other way.
+    pub fn face_neighbours(&self, face: usize) -> [Option<usize>;3]{
+        [0, 1, 2].map(|i| self.half_edges[face * 3 + i].opposite.map(|opposite| opposite / 3))
+    }
+
+    ///True when every edge has exactly two faces running along it in opposite directions.
+    pub fn is_closed(&self) -> bool{
+        self.half_edges.iter().all(|half_edge| half_edge.opposite.is_some())
+            && self.edges.iter().all(|edge| edge.extra_face_indexes.is_empty())
+    }
+}
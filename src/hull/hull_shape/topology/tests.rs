@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::hull::hull_shape::HullShape;
+
+fn check_half_edges(shape: &HullShape){
+    for half_edge in 0..shape.faces.len() * 3{
+        let [start, end] = shape.half_edge_vertices(half_edge);
+        assert_eq!(shape.half_edge_vertices(shape.next_half_edge(half_edge))[0], end);
+        if let Some(opposite) = shape.opposite_half_edge(half_edge){
+            assert_eq!(shape.opposite_half_edge(opposite), Some(half_edge));
+            assert_eq!(shape.half_edge_vertices(opposite), [end, start]);
+            assert_eq!(shape.half_edge_edge(opposite), shape.half_edge_edge(half_edge));
+        }
+    }
+}
+
+#[test]
+fn test_cube_topology(){
+    let shape: HullShape = Mesh::from(shape::Cube::default()).try_into().unwrap();
+
+    assert_eq!(shape.vertex_count(), 8);
+    assert_eq!(shape.edge_count(), 18);
+    assert_eq!(shape.face_count(), 12);
+    assert!(shape.is_closed());
+    assert_eq!(shape.boundary_edges().count(), 0);
+    for edge in 0..shape.edge_count(){
+        assert_eq!(shape.edge_faces(edge).count(), 2);
+    }
+    for vertex in shape.vertices.iter(){
+        assert!(vertex.edge_indices.len() >= 3);
+        assert!(vertex.face_indices.len() >= 3);
+    }
+    check_half_edges(&shape);
+}
+
+#[test]
+fn test_torus_topology(){
+    let shape: HullShape = Mesh::from(shape::Torus::default()).try_into().unwrap();
+
+    //a torus has an Euler characteristic of zero
+    let euler = shape.vertex_count() as i64 - shape.edge_count() as i64 + shape.face_count() as i64;
+    assert_eq!(euler, 0);
+    assert!(shape.is_closed());
+    for face in 0..shape.face_count(){
+        assert!(shape.face_neighbours(face).iter().all(|neighbour| neighbour.is_some()));
+    }
+    check_half_edges(&shape);
+}
+
+#[test]
+fn test_open_mesh_topology(){
+    let shape: HullShape = Mesh::from(shape::Plane{size: 2.0, subdivisions: 2}).try_into().unwrap();
+
+    //a 3 by 3 grid of quads
+    assert_eq!(shape.vertex_count(), 16);
+    assert_eq!(shape.face_count(), 18);
+    assert!(!shape.is_closed());
+    assert_eq!(shape.boundary_edges().count(), 12);
+    for edge in shape.boundary_edges(){
+        assert_eq!(shape.edge_faces(edge).count(), 1);
+    }
+    let boundary_half_edges = (0..shape.face_count() * 3).filter(|half_edge| shape.opposite_half_edge(*half_edge).is_none()).count();
+    assert_eq!(boundary_half_edges, 12);
+    check_half_edges(&shape);
+}
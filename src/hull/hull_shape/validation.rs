@@ -0,0 +1,161 @@
+use std::fmt;
+
+use super::*;
+
+///Problems that keep a shape from being a closed, consistently wound hull.
+///Edges are given by their hull vertex indices and faces by their hull face index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HullDefects{
+    ///Edges shared by more than two faces.
+    pub non_manifold_edges: Vec<[usize;2]>,
+    ///Edges with a face on one side only, they outline holes in the surface.
+    pub boundary_edges: Vec<[usize;2]>,
+    ///Edges whose two faces both run the same way along them, one of the faces is flipped.
+    pub inconsistent_winding_edges: Vec<[usize;2]>,
+    ///Faces without area.
+    pub degenerate_faces: Vec<usize>,
+    ///Triangles of the source mesh, by source vertex indices, dropped because welding merged their corners.
+    pub collapsed_faces: Vec<[usize;3]>
+}
+
+impl HullDefects{
+    ///No defects at all.
+    pub fn is_empty(&self) -> bool{
+        self.non_manifold_edges.is_empty() && self.boundary_edges.is_empty() && self.inconsistent_winding_edges.is_empty()
+            && self.degenerate_faces.is_empty() && self.collapsed_faces.is_empty()
+    }
+
+    ///Closed and consistently wound, so volumes and buoyancy come out right. Degenerate faces do no harm here.
+    pub fn is_watertight(&self) -> bool{
+        self.non_manifold_edges.is_empty() && self.boundary_edges.is_empty() && self.inconsistent_winding_edges.is_empty()
+    }
+}
+
+impl fmt::Display for HullDefects{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        if self.is_empty(){
+            return write!(f, "no defects");
+        }
+        let mut parts = Vec::new();
+        if !self.non_manifold_edges.is_empty(){
+            parts.push(format!("{} non-manifold edges {:?}", self.non_manifold_edges.len(), self.non_manifold_edges));
+        }
+        if !self.boundary_edges.is_empty(){
+            parts.push(format!("{} boundary edges {:?}", self.boundary_edges.len(), self.boundary_edges));
+        }
+        if !self.inconsistent_winding_edges.is_empty(){
+            parts.push(format!("{} inconsistently wound edges {:?}", self.inconsistent_winding_edges.len(), self.inconsistent_winding_edges));
+        }
+        if !self.degenerate_faces.is_empty(){
+            parts.push(format!("{} degenerate faces {:?}", self.degenerate_faces.len(), self.degenerate_faces));
+        }
+        if !self.collapsed_faces.is_empty(){
+            parts.push(format!("{} collapsed source triangles {:?}", self.collapsed_faces.len(), self.collapsed_faces));
+        }
+        return write!(f, "{}", parts.join(", "));
+    }
+}
+
+impl HullShape{
+    ///Checks the shape is a closed manifold with consistent winding and reports everything that is not.
+    pub fn validate(&self) -> HullDefects{
+        let mut defects = HullDefects{collapsed_faces: self.collapsed_faces.clone(), ..default()};
+
+        for (edge_index, edge) in self.edges.iter().enumerate(){
+            if !edge.extra_face_indexes.is_empty(){
+                defects.non_manifold_edges.push(edge.vertex_indexes);
+            }else if edge.face_indexes[1].is_none(){
+                defects.boundary_edges.push(edge.vertex_indexes);
+            }else{
+                //two faces but their half edges could not be paired, so they run the same way
+                let [first, second] = edge.face_indexes.map(|face| face.unwrap());
+                let half_edge = (0..3).map(|i| first * 3 + i).find(|half_edge| self.half_edges[*half_edge].edge_index == edge_index);
+                let paired = half_edge.and_then(|half_edge| self.half_edges[half_edge].opposite).map_or(false, |opposite| opposite / 3 == second);
+                if !paired{
+                    defects.inconsistent_winding_edges.push(edge.vertex_indexes);
+                }
+            }
+        }
+
+        for (face_index, face) in self.faces.iter().enumerate(){
+            let [a, b, c] = face.vertex_indices.map(|index| self.vertices[index].position);
+            let longest = (b - a).length_squared().max((c - b).length_squared()).max((a - c).length_squared());
+            //area relative to the longest edge, so slivers count whatever the scale
+            if (b - a).cross(c - a).length() <= longest * 1e-6{
+                defects.degenerate_faces.push(face_index);
+            }
+        }
+
+        return defects;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use bevy::render::{mesh::{Indices, MeshVertexAttribute}, render_resource::{PrimitiveTopology, VertexFormat}};
+
+    use super::*;
+    use super::super::into::HullShapeIntoError;
+    use super::super::tests::{mesh_from, tetrahedron_positions};
+
+    #[test]
+    fn test_closed_shapes(){
+        for mesh in [Mesh::from(shape::Cube::default()), Mesh::from(shape::Torus::default()), Mesh::from(shape::Cylinder::default())]{
+            let defects = HullShape::from_mesh_validated(&mesh, 1e-5).map(|shape| shape.validate());
+            assert_eq!(defects, Ok(HullDefects::default()));
+        }
+    }
+
+    #[test]
+    fn test_defects(){
+        //tetrahedron missing a face
+        let shape = HullShape::from_mesh(&mesh_from(tetrahedron_positions(), vec![0, 2, 1, 0, 1, 3, 1, 2, 3]), 0.0).unwrap();
+        let defects = shape.validate();
+        assert_eq!(defects.boundary_edges.len(), 3);
+        assert!(!defects.is_watertight());
+
+        //one face flipped
+        let shape = HullShape::from_mesh(&mesh_from(tetrahedron_positions(), vec![0, 2, 1, 0, 1, 3, 1, 2, 3, 0, 2, 3]), 0.0).unwrap();
+        let defects = shape.validate();
+        assert_eq!(defects.inconsistent_winding_edges.len(), 3);
+        assert!(defects.boundary_edges.is_empty());
+
+        //a fin sticking out of an edge, and a sliver
+        let mut positions = tetrahedron_positions();
+        positions.push([1.0, 1.0, -1.0]);
+        positions.push([0.5, 0.5, 0.0]);
+        let shape = HullShape::from_mesh(&mesh_from(positions, vec![0, 2, 1, 0, 1, 3, 1, 2, 3, 0, 3, 2, 1, 2, 4, 1, 2, 5]), 0.0).unwrap();
+        let defects = shape.validate();
+        assert_eq!(defects.non_manifold_edges.len(), 1);
+        assert_eq!(defects.degenerate_faces, vec![5]);
+    }
+
+    #[test]
+    fn test_conversion_errors(){
+        let mut mesh = mesh_from(tetrahedron_positions(), vec![0, 2, 1, 0, 1, 3, 1, 2, 3]);
+        assert!(matches!(HullShape::from_mesh_validated(&mesh, 0.0), Err(HullShapeIntoError::Defects(_))));
+
+        //a closed tetrahedron with a triangle welded away is still watertight
+        let collapsed = mesh_from(tetrahedron_positions(), vec![0, 2, 1, 0, 1, 3, 1, 2, 3, 0, 3, 2, 0, 0, 1]);
+        let shape = HullShape::from_mesh_validated(&collapsed, 0.0).unwrap();
+        assert_eq!(shape.validate().collapsed_faces, vec![[0, 0, 1]]);
+
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 7])));
+        assert_eq!(HullShape::from_mesh(&mesh, 0.0).err(), Some(HullShapeIntoError::IndexOutOfBounds{index: 7, vertex_count: 4}));
+
+        //without indices the vertices are taken three at a time
+        mesh.set_indices(None);
+        assert_eq!(HullShape::from_mesh(&mesh, 0.0).map(|shape| shape.face_count()), Ok(1));
+
+        //bevy checks the format of its own attribute, a look-alike with the same id (0) gets past it
+        let flat_position = MeshVertexAttribute::new("Vertex_Position", 0, VertexFormat::Float32x2);
+        mesh.insert_attribute(flat_position, vec![[0.0f32, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+        assert_eq!(HullShape::from_mesh(&mesh, 0.0).err(), Some(HullShapeIntoError::UnsupportedPositionFormat));
+
+        mesh.remove_attribute(Mesh::ATTRIBUTE_POSITION);
+        assert_eq!(HullShape::from_mesh(&mesh, 0.0).err(), Some(HullShapeIntoError::MissingPositionAttribute));
+
+        let lines = Mesh::new(PrimitiveTopology::LineList);
+        assert_eq!(HullShape::from_mesh(&lines, 0.0).err(), Some(HullShapeIntoError::WrongTopologyType));
+    }
+}
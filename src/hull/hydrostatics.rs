@@ -0,0 +1,296 @@
+use std::fmt::Write as _;
+use std::io;
+
+use crate::geometry::Plane;
+
+use super::clipping::ClipTolerance;
+
+use super::*;
+
+///Describes the sweep done by [`Hull::hydrostatics`]. All directions are in the hull's local space.
+///Drafts are measured along `up` from the lowest point of the hull, angles are in radians.
+///Positive heel puts the starboard side (`longitudinal` cross `up`) down, positive trim puts the bow down.
+#[derive(Clone, Debug)]
+pub struct HydrostaticsConfig{
+    pub up: Vec3,
+    ///Bow direction of the hull.
+    pub longitudinal: Vec3,
+    pub drafts: Vec<f32>,
+    pub heel_angles: Vec<f32>,
+    pub trim_angles: Vec<f32>,
+    ///Centre of gravity used for GM and the righting arm.
+    pub centre_of_gravity: Vec3,
+    ///Fluid density in kg/m^3.
+    pub density: f32,
+    ///Tolerance the hull is clipped with at each draft, heel and trim.
+    pub clip_tolerance: ClipTolerance
+}
+
+impl Default for HydrostaticsConfig{
+    fn default() -> Self {
+        Self {
+            up: Vec3::Y,
+            longitudinal: Vec3::X,
+            drafts: Vec::new(),
+            heel_angles: vec![0.0],
+            trim_angles: vec![0.0],
+            centre_of_gravity: Vec3::ZERO,
+            density: 1000.0,
+            clip_tolerance: ClipTolerance::default()
+        }
+    }
+}
+
+///Hydrostatic properties of one draft, heel and trim combination.
+///Heights (KB, KM) are measured along the hull's up axis from its lowest point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HydrostaticsRow{
+    pub draft: f32,
+    pub heel: f32,
+    pub trim: f32,
+    ///Submerged volume.
+    pub volume: f32,
+    ///Mass of the displaced fluid.
+    pub displacement: f32,
+    pub centre_of_buoyancy: Vec3,
+    pub waterplane_area: f32,
+    pub centre_of_flotation: Vec3,
+    pub kb: f32,
+    ///Transverse metacentric radius, I_T / V.
+    pub bm_transverse: f32,
+    ///Longitudinal metacentric radius, I_L / V.
+    pub bm_longitudinal: f32,
+    pub km_transverse: f32,
+    pub km_longitudinal: f32,
+    ///Transverse metacentric height for the configured centre of gravity.
+    pub gm_transverse: f32,
+    ///Righting arm, positive when the hull pushes itself back upright.
+    pub gz: f32
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HydrostaticsTable{
+    pub rows: Vec<HydrostaticsRow>
+}
+
+const CSV_HEADER: &str = "draft,heel,trim,volume,displacement,\
+cb_x,cb_y,cb_z,waterplane_area,cf_x,cf_y,cf_z,\
+kb,bm_transverse,bm_longitudinal,km_transverse,km_longitudinal,gm_transverse,gz";
+
+//bisection steps when solving the draft for a displacement, far below f32 precision for any hull
+const DRAFT_ITERATIONS: usize = 48;
+
+impl HydrostaticsTable{
+    pub fn to_csv(&self) -> String{
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for row in self.rows.iter(){
+            let _ = writeln!(csv, "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                row.draft, row.heel, row.trim, row.volume, row.displacement,
+                row.centre_of_buoyancy.x, row.centre_of_buoyancy.y, row.centre_of_buoyancy.z,
+                row.waterplane_area,
+                row.centre_of_flotation.x, row.centre_of_flotation.y, row.centre_of_flotation.z,
+                row.kb, row.bm_transverse, row.bm_longitudinal, row.km_transverse, row.km_longitudinal,
+                row.gm_transverse, row.gz
+            );
+        }
+        return csv;
+    }
+
+    pub fn write_csv<W: io::Write>(&self, mut writer: W) -> io::Result<()>{
+        writer.write_all(self.to_csv().as_bytes())
+    }
+}
+
+impl Hull{
+    ///Water plane in the hull's local space for a draft, heel and trim.
+    pub(super) fn attitude_plane(&self, up: Vec3, longitudinal: Vec3, draft: f32, heel: f32, trim: f32) -> Plane{
+        let up = up.normalize();
+        let longitudinal = (longitudinal - up * longitudinal.dot(up)).normalize();
+        let starboard = longitudinal.cross(up);
+
+        //turning the hull is the same as turning the water the opposite way
+        let rotation = Quat::from_axis_angle(starboard, -trim) * Quat::from_axis_angle(longitudinal, heel);
+        return Plane{
+            normal: rotation.inverse() * up,
+            zero_point: up * (self.keel_height(up) + draft)
+        };
+    }
+
+    ///Height of the hull's lowest point along `up`.
+    pub(super) fn keel_height(&self, up: Vec3) -> f32{
+        let up = up.normalize();
+        self.shape.vertices.iter()
+            .map(|vertex| vertex.position.dot(up))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    ///Sweeps the hull through every combination of drafts, heel and trim angles in `config`.
+    pub fn hydrostatics(&self, config: &HydrostaticsConfig) -> HydrostaticsTable{
+        let up = config.up.normalize();
+        let keel = self.keel_height(up);
+        let mut table = HydrostaticsTable::default();
+
+        for draft in config.drafts.iter(){
+            for trim in config.trim_angles.iter(){
+                for heel in config.heel_angles.iter(){
+                    let plane = self.attitude_plane(up, config.longitudinal, *draft, *heel, *trim);
+                    let clipped = self.clip_with_tolerance(&plane, config.clip_tolerance);
+                    table.rows.push(hydrostatics_row(&clipped, config, keel, *draft, *heel, *trim));
+                }
+            }
+        }
+        return table;
+    }
+
+    ///The classic GZ curve, one row for each of `config.heel_angles` at the given trim.
+    ///The displacement is held at `displacement` by solving the draft again at every heel,
+    ///the row's draft is then the depth of the hull's lowest point below the tilted water, measured along its normal.
+    ///Heel angles run all the way round, past 90 degrees the hull lies on its side and at 180 it floats upside down.
+    ///A displacement the hull cannot carry gives the rows of the fully submerged hull.
+    pub fn righting_arm_curve(&self, config: &HydrostaticsConfig, displacement: f32, trim: f32) -> Vec<HydrostaticsRow>{
+        let up = config.up.normalize();
+        let keel = self.keel_height(up);
+        let required_volume = displacement / config.density;
+
+        let mut rows = Vec::with_capacity(config.heel_angles.len());
+        for heel in config.heel_angles.iter(){
+            let normal = self.attitude_plane(up, config.longitudinal, 0.0, *heel, trim).normal;
+
+            //submerged volume only grows as the water rises along its normal
+            let (bottom, top) = self.shape.vertices.iter()
+                .map(|vertex| vertex.position.dot(normal))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), height| (low.min(height), high.max(height)));
+            let (mut low, mut high) = (bottom, top);
+            for _ in 0..DRAFT_ITERATIONS{
+                let middle = (low + high) / 2.0;
+                if self.clip_with_tolerance(&Plane{normal, zero_point: normal * middle}, config.clip_tolerance).volume() < required_volume{
+                    low = middle;
+                }else{
+                    high = middle;
+                }
+            }
+
+            let height = (low + high) / 2.0;
+            let draft = height - bottom;
+            let clipped = self.clip_with_tolerance(&Plane{normal, zero_point: normal * height}, config.clip_tolerance);
+            rows.push(hydrostatics_row(&clipped, config, keel, draft, *heel, trim));
+        }
+        return rows;
+    }
+}
+
+fn hydrostatics_row(clipped: &ClippedHull, config: &HydrostaticsConfig, keel: f32, draft: f32, heel: f32, trim: f32) -> HydrostaticsRow{
+    let up = config.up.normalize();
+    let normal = clipped.plane.normal.normalize();
+    let (volume, centre_of_buoyancy) = clipped.volume_and_centroid();
+    let waterplane = clipped.waterplane(config.longitudinal);
+
+    let mut row = HydrostaticsRow{
+        draft, heel, trim, volume,
+        displacement: volume * config.density,
+        centre_of_buoyancy,
+        waterplane_area: waterplane.area,
+        centre_of_flotation: waterplane.centre_of_flotation,
+        kb: centre_of_buoyancy.dot(up) - keel,
+        ..default()
+    };
+    if volume <= f32::EPSILON{
+        return row;
+    }
+
+    row.bm_transverse = waterplane.transverse_moment / volume;
+    row.bm_longitudinal = waterplane.longitudinal_moment / volume;
+    row.km_transverse = (centre_of_buoyancy + normal * row.bm_transverse).dot(up) - keel;
+    row.km_longitudinal = (centre_of_buoyancy + normal * row.bm_longitudinal).dot(up) - keel;
+    row.gm_transverse = row.km_transverse - (config.centre_of_gravity.dot(up) - keel);
+
+    //horizontal distance between buoyancy and gravity, measured towards starboard in the water's frame
+    let longitudinal = (config.longitudinal - normal * config.longitudinal.dot(normal)).normalize_or_zero();
+    row.gz = (centre_of_buoyancy - config.centre_of_gravity).dot(longitudinal.cross(normal));
+    return row;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_box_hydrostatics(){
+        //4 long, 2 high and 2 wide with the keel at y = -1
+        let hull = Hull::try_from(Mesh::from(shape::Box::new(4.0, 2.0, 2.0))).unwrap();
+        let config = HydrostaticsConfig{
+            drafts: vec![1.0],
+            heel_angles: vec![0.0, 0.2],
+            centre_of_gravity: Vec3::new(0.0, -0.5, 0.0),
+            ..default()
+        };
+
+        let table = hull.hydrostatics(&config);
+        assert_eq!(table.rows.len(), 2);
+
+        let upright = table.rows[0];
+        assert!((upright.volume - 8.0).abs() < 1e-4);
+        assert!((upright.displacement - 8000.0).abs() < 0.1);
+        assert!((upright.kb - 0.5).abs() < 1e-4);
+        //B * B / (12 * T) for a box
+        assert!((upright.bm_transverse - 4.0 / 12.0).abs() < 1e-4);
+        assert!((upright.km_transverse - (0.5 + 4.0 / 12.0)).abs() < 1e-4);
+        assert!(upright.gz.abs() < 1e-4);
+
+        //with G low in the box heeling to starboard has to push back
+        let heeled = table.rows[1];
+        assert!(heeled.gz > 0.0);
+
+        let csv = table.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert_eq!(csv.lines().next().unwrap().split(',').count(), csv.lines().nth(1).unwrap().split(',').count());
+    }
+
+    #[test]
+    fn test_righting_arm_curve(){
+        let hull = Hull::try_from(Mesh::from(shape::Box::new(4.0, 2.0, 2.0))).unwrap();
+        let config = HydrostaticsConfig{
+            heel_angles: vec![0.0, 0.2, 0.5],
+            centre_of_gravity: Vec3::new(0.0, -0.5, 0.0),
+            ..default()
+        };
+
+        let curve = hull.righting_arm_curve(&config, 8000.0, 0.0);
+        assert_eq!(curve.len(), 3);
+        for row in curve.iter(){
+            assert!((row.displacement - 8000.0).abs() < 0.1, "{}", row.displacement);
+        }
+        assert!((curve[0].draft - 1.0).abs() < 1e-4);
+        assert!(curve[0].gz.abs() < 1e-4);
+
+        //wall sided box, GZ = sin(heel) * (GM + BM / 2 * tan(heel)^2) while the deck stays dry
+        let heel: f32 = 0.2;
+        let expected_gz = heel.sin() * (1.0 / 3.0 + 1.0 / 6.0 * heel.tan().powi(2));
+        assert!((curve[1].gz - expected_gz).abs() < 1e-3, "{}", curve[1].gz);
+        assert!(curve[2].gz > curve[1].gz);
+    }
+
+    #[test]
+    fn test_righting_arm_curve_all_the_way_round(){
+        let hull = Hull::try_from(Mesh::from(shape::Box::new(4.0, 2.0, 2.0))).unwrap();
+        let config = HydrostaticsConfig{
+            heel_angles: (0..=12).map(|step| step as f32 * std::f32::consts::PI / 12.0).collect(),
+            centre_of_gravity: Vec3::new(0.0, -0.5, 0.0),
+            ..default()
+        };
+
+        let curve = hull.righting_arm_curve(&config, 8000.0, 0.0);
+        for row in curve.iter(){
+            assert!(row.draft.is_finite() && row.gz.is_finite(), "{:?}", row);
+            assert!((row.displacement - 8000.0).abs() < 0.1, "{:?}", row);
+        }
+        //the square box floats half in at any heel, so on its side and upside down it is still 1 deep
+        assert!((curve[6].draft - 1.0).abs() < 1e-4);
+        assert!((curve[12].draft - 1.0).abs() < 1e-4);
+        assert!(curve[0].gz.abs() < 1e-4);
+        assert!(curve[12].gz.abs() < 1e-4, "{}", curve[12].gz);
+        //G below the middle keeps the box righting itself until it is upside down
+        assert!(curve[1..12].iter().all(|row| row.gz > 0.0));
+    }
+}
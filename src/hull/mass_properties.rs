@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+///Volume integrals of a closed triangle mesh, taken relative to an apex point.
+///Each triangle forms a signed tetrahedron with the apex, by the divergence theorem
+///their sum is the enclosed solid no matter where the apex sits.
+pub(super) struct VolumeIntegral{
+    apex: Vec3,
+    volume: f32,
+    //integral of (p - apex) over the volume
+    first_moment: Vec3,
+    //integral of (p - apex)(p - apex)^T over the volume
+    second_moment: Mat3
+}
+
+impl VolumeIntegral{
+    pub(super) fn new(apex: Vec3) -> Self{
+        Self { apex, volume: 0.0, first_moment: Vec3::ZERO, second_moment: Mat3::ZERO }
+    }
+
+    pub(super) fn add_triangle(&mut self, a: Vec3, b: Vec3, c: Vec3){
+        let a = a - self.apex;
+        let b = b - self.apex;
+        let c = c - self.apex;
+
+        let determinant = a.dot(b.cross(c));
+        self.volume += determinant / 6.0;
+        self.first_moment += determinant / 24.0 * (a + b + c);
+
+        //covariance of the canonical tetrahedron mapped through [a b c]
+        let columns = Mat3::from_cols(a, b, c);
+        let canonical = Mat3::from_cols_array(&[
+            2.0, 1.0, 1.0,
+            1.0, 2.0, 1.0,
+            1.0, 1.0, 2.0
+        ]) * (1.0 / 120.0);
+        self.second_moment += determinant * columns * canonical * columns.transpose();
+    }
+
+    pub(super) fn volume(&self) -> f32{
+        return self.volume;
+    }
+
+    pub(super) fn centroid(&self) -> Vec3{
+        if self.volume.abs() <= f32::EPSILON{
+            return self.apex;
+        }
+        return self.apex + self.first_moment / self.volume;
+    }
+
+    pub(super) fn inertia_tensor(&self) -> Mat3{
+        if self.volume.abs() <= f32::EPSILON{
+            return Mat3::ZERO;
+        }
+        //move the second moment from the apex to the centroid (parallel axis theorem)
+        let offset = self.first_moment / self.volume;
+        let covariance = self.second_moment - self.volume * outer_product(offset, offset);
+        let trace = covariance.x_axis.x + covariance.y_axis.y + covariance.z_axis.z;
+        return Mat3::from_diagonal(Vec3::splat(trace)) - covariance;
+    }
+}
+
+fn outer_product(a: Vec3, b: Vec3) -> Mat3{
+    Mat3::from_cols(a * b.x, a * b.y, a * b.z)
+}
+
+impl hull_shape::HullShape{
+    fn volume_integral(&self) -> VolumeIntegral{
+        //integrating around the mean vertex keeps the tetrahedrons small
+        let mut apex = Vec3::ZERO;
+        for vertex in self.vertices.iter(){
+            apex += vertex.position;
+        }
+        if !self.vertices.is_empty(){
+            apex /= self.vertices.len() as f32;
+        }
+
+        let mut integral = VolumeIntegral::new(apex);
+        for face in self.faces.iter(){
+            let [a, b, c] = face.vertex_indices.map(|index| self.vertices[index].position);
+            integral.add_triangle(a, b, c);
+        }
+        return integral;
+    }
+
+    ///Volume enclosed by the hull, assumes a closed mesh with outward facing triangles.
+    pub fn volume(&self) -> f32{
+        self.volume_integral().volume()
+    }
+
+    ///Centroid of the enclosed volume.
+    pub fn centroid(&self) -> Vec3{
+        self.volume_integral().centroid()
+    }
+
+    ///Inertia tensor of the enclosed volume around its centroid, for a density of 1.
+    ///Multiply by the density to get the inertia of a solid body.
+    pub fn inertia_tensor(&self) -> Mat3{
+        self.volume_integral().inertia_tensor()
+    }
+}
+
+impl ClippedHull{
+    fn volume_integral(&self) -> VolumeIntegral{
+        //taking the apex on the clipping plane keeps the cap's tetrahedrons flat,
+        //so the result holds up even when the cap could not be fully closed
+        let mut integral = VolumeIntegral::new(self.plane.zero_point);
+        for triangle in self.indices.chunks_exact(3).chain(self.cap_indices.chunks_exact(3)).chain(self.wall_indices.chunks_exact(3)){
+            integral.add_triangle(self.position(triangle[0]), self.position(triangle[1]), self.position(triangle[2]));
+        }
+        return integral;
+    }
+
+    ///Volume of the clipped piece including its cap.
+    pub fn volume(&self) -> f32{
+        self.volume_integral().volume()
+    }
+
+    ///Centroid of the clipped piece, for a submerged hull this is the centre of buoyancy.
+    pub fn centroid(&self) -> Vec3{
+        self.volume_integral().centroid()
+    }
+
+    ///Inertia tensor of the clipped piece around its centroid, for a density of 1.
+    pub fn inertia_tensor(&self) -> Mat3{
+        self.volume_integral().inertia_tensor()
+    }
+
+    ///Volume and centroid of the clipped piece from a single pass over its triangles.
+    pub fn volume_and_centroid(&self) -> (f32, Vec3){
+        let integral = self.volume_integral();
+        return (integral.volume(), integral.centroid());
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::hull::*;
+use crate::geometry::*;
+
+fn assert_close(a: f32, b: f32){
+    assert!((a - b).abs() < 1e-4, "{} is not close to {}", a, b);
+}
+
+#[test]
+fn test_cube_properties(){
+    let shape: hull_shape::HullShape = Mesh::from(shape::Cube::new(2.0)).try_into().unwrap();
+
+    assert_close(shape.volume(), 8.0);
+    assert!(shape.centroid().length() < 1e-5);
+
+    //solid cube of side s and mass m has m*s^2/6 around every axis
+    let inertia = shape.inertia_tensor();
+    assert_close(inertia.x_axis.x, 8.0 * 4.0 / 6.0);
+    assert_close(inertia.y_axis.y, 8.0 * 4.0 / 6.0);
+    assert_close(inertia.z_axis.z, 8.0 * 4.0 / 6.0);
+    assert_close(inertia.x_axis.y, 0.0);
+    assert_close(inertia.y_axis.z, 0.0);
+}
+
+#[test]
+fn test_clipped_cube_properties(){
+    let hull = Hull::try_from(Mesh::from(shape::Cube::new(2.0))).unwrap();
+
+    let clipped = hull.clip_with_plane(&Plane{
+        normal: Vec3::Y,
+        zero_point: Vec3::ZERO
+    });
+
+    //lower half of the cube, a 2x1x2 box centered at y = -0.5
+    assert_close(clipped.volume(), 4.0);
+    assert!((clipped.centroid() - Vec3::new(0.0, -0.5, 0.0)).length() < 1e-5);
+
+    let inertia = clipped.inertia_tensor();
+    assert_close(inertia.x_axis.x, 4.0 * (1.0 + 4.0) / 12.0);
+    assert_close(inertia.y_axis.y, 4.0 * (4.0 + 4.0) / 12.0);
+    assert_close(inertia.z_axis.z, 4.0 * (4.0 + 1.0) / 12.0);
+}
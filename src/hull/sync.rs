@@ -0,0 +1,367 @@
+use bevy::{prelude::*, asset::HandleId, ecs::{event::ManualEventReader, system::SystemParam}, math::Affine3A, render::{render_resource::PrimitiveTopology, mesh::Indices}};
+use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::parry::{shape::{Shape, TypedShape}, math::{Isometry, Real}};
+use std::{borrow::Cow, collections::HashSet};
+
+use super::{Hull, hull_shape::{HullShape, into::DEFAULT_WELD_TOLERANCE, decimate::DecimationSettings}};
+
+pub struct HullSyncPlugin;
+
+impl Plugin for HullSyncPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, hull_from_mesh_system);
+    }
+}
+
+///Where a [`HullFromMesh`] takes its triangles from, lets the physics hull be a simpler shape than the one on screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum HullSource{
+    ///The entity's own mesh, or the first mesh among its descendants when it has none.
+    #[default]
+    OwnMesh,
+    ///A separate mesh asset, such as a low poly copy of the visible one, laid out in the entity's space.
+    ///[`HullFromMesh::decimation`] can simplify the visible mesh instead of modelling one.
+    Mesh(Handle<Mesh>),
+    ///The mesh of the first descendant with this [`Name`], or the first mesh under it.
+    ///glTF scenes name their entities after the file's nodes, so a hull node can sit next to the visible ones.
+    NamedChild(String),
+    ///The entity's Rapier [`Collider`], balls, capsules, cylinders and cones are tessellated.
+    ///The parts of a compound collider are added together, so they should not overlap.
+    Collider
+}
+
+///Builds the entity's [`Hull`] from its [`HullSource`] once the asset has loaded, and again whenever the asset or collider changes.
+///Meshes found under the entity, like those of a glTF scene root, are placed where they sit relative to it.
+///Sources that cannot be converted are logged and skipped.
+#[derive(Component, Clone, Debug)]
+pub struct HullFromMesh{
+    pub source: HullSource,
+    pub weld_tolerance: f32,
+    ///Run [`HullShape::repair`] on the converted shape.
+    pub repair: bool,
+    ///Simplify the converted shape with [`HullShape::decimate`], a cheap buoyancy proxy for a detailed mesh.
+    pub decimation: Option<DecimationSettings>,
+    //what the current hull was built from, or the last one that failed
+    built_from: Option<BuiltFrom>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BuiltFrom{
+    Mesh(HandleId),
+    Collider
+}
+
+impl HullFromMesh{
+    pub fn new(weld_tolerance: f32, repair: bool) -> Self{
+        Self { source: HullSource::OwnMesh, weld_tolerance, repair, decimation: None, built_from: None }
+    }
+
+    ///Default settings, taking the triangles from `source`.
+    pub fn from_source(source: HullSource) -> Self{
+        Self { source, ..default() }
+    }
+}
+
+impl Default for HullFromMesh{
+    fn default() -> Self {
+        Self::new(DEFAULT_WELD_TOLERANCE, false)
+    }
+}
+
+//subdivisions used to tessellate round collider shapes
+const COLLIDER_SUBDIVISIONS: u32 = 16;
+
+//Depth first search for the first descendant of an entity that matches
+fn find_descendant(
+    entity: Entity,
+    children_query: &Query<&Children>,
+    matches: &impl Fn(Entity) -> bool
+) -> Option<Entity>{
+    let children = children_query.get(entity).ok()?;
+    for child in children.iter(){
+        if matches(*child){
+            return Some(*child);
+        }
+        if let Some(found) = find_descendant(*child, children_query, matches){
+            return Some(found);
+        }
+    }
+    return None;
+}
+
+//The entity's own mesh or the first one under it, with its transform relative to `root`
+fn find_mesh<'a>(
+    entity: Entity,
+    root: Entity,
+    children_query: &Query<&Children>,
+    mesh_query: &'a Query<(&Handle<Mesh>, Option<&GlobalTransform>)>,
+    root_transform: Affine3A
+) -> Option<(&'a Handle<Mesh>, Affine3A)>{
+    let found = if mesh_query.contains(entity){
+        entity
+    }else{
+        find_descendant(entity, children_query, &|child| mesh_query.contains(child))?
+    };
+    let (handle, mesh_transform) = mesh_query.get(found).ok()?;
+    if found == root{
+        return Some((handle, Affine3A::IDENTITY));
+    }
+    let mesh_transform = mesh_transform.map_or(Affine3A::IDENTITY, |transform| transform.affine());
+    return Some((handle, root_transform.inverse() * mesh_transform));
+}
+
+//Appends a Rapier shape's triangles, returns false for shapes that do not enclose a volume
+fn append_shape_triangles(shape: &dyn Shape, isometry: &Isometry<Real>, positions: &mut Vec<[f32;3]>, indices: &mut Vec<u32>) -> bool{
+    let (points, triangles) = match shape.as_typed_shape(){
+        TypedShape::Ball(ball) => ball.to_trimesh(COLLIDER_SUBDIVISIONS, COLLIDER_SUBDIVISIONS / 2),
+        TypedShape::Cuboid(cuboid) => cuboid.to_trimesh(),
+        TypedShape::Capsule(capsule) => capsule.to_trimesh(COLLIDER_SUBDIVISIONS, COLLIDER_SUBDIVISIONS / 2),
+        TypedShape::Cylinder(cylinder) => cylinder.to_trimesh(COLLIDER_SUBDIVISIONS),
+        TypedShape::Cone(cone) => cone.to_trimesh(COLLIDER_SUBDIVISIONS),
+        TypedShape::ConvexPolyhedron(polyhedron) => polyhedron.to_trimesh(),
+        TypedShape::TriMesh(trimesh) => (trimesh.vertices().to_vec(), trimesh.indices().to_vec()),
+        TypedShape::Compound(compound) => {
+            for (part_isometry, part) in compound.shapes(){
+                if !append_shape_triangles(part.as_ref(), &(isometry * part_isometry), positions, indices){
+                    return false;
+                }
+            }
+            return true;
+        },
+        _ => return false
+    };
+
+    let offset = positions.len() as u32;
+    positions.extend(points.iter().map(|point| {
+        let point = isometry * point;
+        [point.x, point.y, point.z]
+    }));
+    indices.extend(triangles.iter().flatten().map(|index| index + offset));
+    return true;
+}
+
+///Triangle mesh of a collider's shape in its entity's space, `None` for shapes that do not enclose a volume.
+pub fn collider_mesh(collider: &Collider) -> Option<Mesh>{
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    if !append_shape_triangles(collider.raw.as_ref(), &Isometry::identity(), &mut positions, &mut indices){
+        return None;
+    }
+
+    //the raw shape carries the entity's scale, the hull lives in the unscaled space
+    let scale = collider.scale();
+    for position in positions.iter_mut(){
+        *position = (Vec3::from_array(*position) / scale).to_array();
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    return Some(mesh);
+}
+
+pub(super) fn build_hull(mesh: &Mesh, settings: &HullFromMesh, hull_from_source: Affine3A) -> Result<Hull, super::hull_shape::into::HullShapeIntoError>{
+    let mut shape = HullShape::from_mesh(mesh, settings.weld_tolerance)?;
+    if hull_from_source != Affine3A::IDENTITY{
+        for vertex in shape.vertices.iter_mut(){
+            vertex.position = hull_from_source.transform_point3(vertex.position);
+        }
+        //a mirroring transform turns the faces inside out
+        if hull_from_source.matrix3.determinant() < 0.0{
+            for face in shape.faces.iter_mut(){
+                face.vertex_indices.swap(1, 2);
+                face.source_indices.swap(1, 2);
+            }
+            shape.build_topology();
+        }
+    }
+    if settings.repair{
+        shape.repair();
+    }
+    if let Some(decimation) = &settings.decimation{
+        shape.decimate(decimation);
+    }
+    return Ok(Hull::from(shape));
+}
+
+//Mesh assets and their change events, either may be missing when the app runs without assets
+#[derive(SystemParam)]
+struct MeshAssets<'w, 's>{
+    meshes: Option<Res<'w, Assets<Mesh>>>,
+    events: Option<Res<'w, Events<AssetEvent<Mesh>>>>,
+    event_reader: Local<'s, ManualEventReader<AssetEvent<Mesh>>>
+}
+
+type HullFromMeshQuery<'w, 's> = Query<'w, 's, (Entity, &'static mut HullFromMesh, Option<&'static GlobalTransform>, Option<Ref<'static, Collider>>)>;
+
+fn hull_from_mesh_system(
+    mut commands: Commands,
+    mut mesh_assets: MeshAssets,
+    mut hull_query: HullFromMeshQuery,
+    children_query: Query<&Children>,
+    mesh_query: Query<(&Handle<Mesh>, Option<&GlobalTransform>)>,
+    name_query: Query<&Name>
+){
+    let mut modified = HashSet::<HandleId>::new();
+    if let Some(mesh_events) = &mesh_assets.events{
+        for event in mesh_assets.event_reader.iter(mesh_events){
+            if let AssetEvent::Modified{handle} = event{
+                modified.insert(handle.id());
+            }
+        }
+    }
+
+    for (entity, mut hull_from_mesh, global_transform, collider) in hull_query.iter_mut(){
+        let entity_transform = global_transform.map_or(Affine3A::IDENTITY, |transform| transform.affine());
+
+        let (mesh, built_from, hull_from_source) = match &hull_from_mesh.source{
+            HullSource::Collider => {
+                let Some(collider) = collider else {continue};
+                if hull_from_mesh.built_from == Some(BuiltFrom::Collider) && !collider.is_changed(){
+                    continue;
+                }
+                hull_from_mesh.built_from = Some(BuiltFrom::Collider);
+                let Some(mesh) = collider_mesh(&collider) else {
+                    error!("Could not build a hull for {:?} from its collider: the shape does not enclose a volume", entity);
+                    continue;
+                };
+                (Cow::Owned(mesh), BuiltFrom::Collider, Affine3A::IDENTITY)
+            },
+            source => {
+                let found = match source{
+                    HullSource::Mesh(handle) => Some((handle, Affine3A::IDENTITY)),
+                    HullSource::NamedChild(name) => {
+                        find_descendant(entity, &children_query, &|child| name_query.get(child).is_ok_and(|child_name| child_name.as_str() == name))
+                        .and_then(|named| find_mesh(named, entity, &children_query, &mesh_query, entity_transform))
+                    },
+                    _ => find_mesh(entity, entity, &children_query, &mesh_query, entity_transform)
+                };
+                let Some((handle, hull_from_source)) = found else {continue};
+
+                let id = handle.id();
+                if hull_from_mesh.built_from == Some(BuiltFrom::Mesh(id)) && !modified.contains(&id){
+                    continue;
+                }
+                //not loaded yet
+                let Some(mesh) = mesh_assets.meshes.as_ref().and_then(|meshes| meshes.get(handle)) else {continue};
+                (Cow::Borrowed(mesh), BuiltFrom::Mesh(id), hull_from_source)
+            }
+        };
+
+        hull_from_mesh.built_from = Some(built_from);
+        match build_hull(&mesh, &hull_from_mesh, hull_from_source){
+            Ok(hull) => {
+                commands.entity(entity).insert(hull);
+            },
+            Err(error) => {
+                error!("Could not build a hull for {:?} from {:?}: {}", entity, hull_from_mesh.source, error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use bevy::asset::AssetPlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_hull_follows_mesh(){
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Mesh>()
+        .add_plugins(HullSyncPlugin);
+
+        let handle = app.world.resource_mut::<Assets<Mesh>>().add(shape::Cube::new(2.0).into());
+        let entity = app.world.spawn((handle.clone(), HullFromMesh::default())).id();
+
+        app.update();
+        let volume = app.world.get::<Hull>(entity).unwrap().shape().volume();
+        assert!((volume - 8.0).abs() < 1e-4);
+
+        //swapping the asset's contents rebuilds the hull
+        let _ = app.world.resource_mut::<Assets<Mesh>>().set(&handle, shape::Cube::new(1.0).into());
+        app.update();
+        app.update();
+        let volume = app.world.get::<Hull>(entity).unwrap().shape().volume();
+        assert!((volume - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_scene_root(){
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Mesh>()
+        .add_plugins(HullSyncPlugin);
+
+        //a child mesh moved up by one
+        let handle = app.world.resource_mut::<Assets<Mesh>>().add(shape::Cube::new(2.0).into());
+        let root = app.world.spawn((GlobalTransform::default(), HullFromMesh::default())).id();
+        let child = app.world.spawn((handle, GlobalTransform::from_translation(Vec3::Y))).id();
+        app.world.entity_mut(root).push_children(&[child]);
+
+        app.update();
+        let shape = app.world.get::<Hull>(root).unwrap().shape();
+        assert!((shape.centroid() - Vec3::Y).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_named_child(){
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Mesh>()
+        .add_plugins(HullSyncPlugin);
+
+        //the visible mesh comes first, the hull node holds its mesh one level further down like a glTF primitive
+        let visible = app.world.resource_mut::<Assets<Mesh>>().add(shape::Cube::new(2.0).into());
+        let low_poly = app.world.resource_mut::<Assets<Mesh>>().add(shape::Cube::new(1.0).into());
+        let root = app.world.spawn((GlobalTransform::default(), HullFromMesh::from_source(HullSource::NamedChild("hull".to_string())))).id();
+        let visible_child = app.world.spawn((visible, GlobalTransform::default())).id();
+        let hull_node = app.world.spawn((Name::new("hull"), GlobalTransform::default())).id();
+        let primitive = app.world.spawn((low_poly, GlobalTransform::from_translation(Vec3::X))).id();
+        app.world.entity_mut(hull_node).push_children(&[primitive]);
+        app.world.entity_mut(root).push_children(&[visible_child, hull_node]);
+
+        app.update();
+        let shape = app.world.get::<Hull>(root).unwrap().shape();
+        assert!((shape.volume() - 1.0).abs() < 1e-4);
+        assert!((shape.centroid() - Vec3::X).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_decimated_proxy(){
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Mesh>()
+        .add_plugins(HullSyncPlugin);
+
+        let handle = app.world.resource_mut::<Assets<Mesh>>().add(shape::UVSphere::default().into());
+        let hull_from_mesh = HullFromMesh{decimation: Some(DecimationSettings::new(100)), ..default()};
+        let entity = app.world.spawn((handle, hull_from_mesh)).id();
+
+        app.update();
+        let shape = app.world.get::<Hull>(entity).unwrap().shape();
+        assert!(shape.face_count() <= 100);
+        assert!(shape.is_closed());
+    }
+
+    #[test]
+    fn test_collider_source(){
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+        .add_plugins(HullSyncPlugin);
+
+        let entity = app.world.spawn((Collider::cuboid(1.0, 0.5, 1.0), HullFromMesh::from_source(HullSource::Collider))).id();
+        app.update();
+        let volume = app.world.get::<Hull>(entity).unwrap().shape().volume();
+        assert!((volume - 4.0).abs() < 1e-4);
+
+        //a changed collider rebuilds the hull
+        *app.world.get_mut::<Collider>(entity).unwrap() = Collider::ball(1.0);
+        app.update();
+        let shape = app.world.get::<Hull>(entity).unwrap().shape();
+        assert!(shape.is_closed());
+        assert!(shape.volume() > 3.5 && shape.volume() < 4.0 / 3.0 * std::f32::consts::PI);
+    }
+}
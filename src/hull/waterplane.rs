@@ -0,0 +1,82 @@
+use super::*;
+
+///Properties of the waterline polygon cut out by the clipping plane.
+///The second moments are taken around axes through the centre of flotation,
+///dividing them by the displaced volume gives the metacentric radii (BM).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Waterplane{
+    pub area: f32,
+    ///Centroid of the waterplane, the point the hull heels and trims around.
+    pub centre_of_flotation: Vec3,
+    ///Second moment of area around the longitudinal axis, governs roll stability.
+    pub transverse_moment: f32,
+    ///Second moment of area around the transverse axis, governs pitch stability.
+    pub longitudinal_moment: f32
+}
+
+impl ClippedHull{
+    ///Waterplane of the cut, `longitudinal` is the hull's bow direction and gets projected onto the plane.
+    pub fn waterplane(&self, longitudinal: Vec3) -> Waterplane{
+        let normal = self.plane.normal.normalize();
+        let mut longitudinal = (longitudinal - normal * longitudinal.dot(normal)).normalize_or_zero();
+        if longitudinal == Vec3::ZERO{
+            //bow direction along the normal, any in plane direction will do
+            longitudinal = self.plane.basis().0;
+        }
+        let transverse = normal.cross(longitudinal);
+
+        //integrals over the cap triangles in (longitudinal, transverse) coordinates around the plane's zero point
+        let mut area = 0.0;
+        let mut first_moment = Vec2::ZERO;
+        let mut second_moment = Vec2::ZERO;
+        for triangle in self.cap_indices.chunks_exact(3){
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| {
+                let offset = self.position(index) - self.plane.zero_point;
+                Vec2::new(offset.dot(longitudinal), offset.dot(transverse))
+            });
+
+            let triangle_area = (b - a).perp_dot(c - a) / 2.0;
+            area += triangle_area;
+            first_moment += triangle_area * (a + b + c) / 3.0;
+            second_moment += triangle_area / 6.0 * (a * a + b * b + c * c + a * b + b * c + c * a);
+        }
+
+        if area.abs() <= f32::EPSILON{
+            return Waterplane{centre_of_flotation: self.plane.zero_point, ..default()};
+        }
+
+        //move the second moments to the centre of flotation (parallel axis theorem)
+        let centroid = first_moment / area;
+        let central_moment = second_moment - area * centroid * centroid;
+
+        return Waterplane{
+            area,
+            centre_of_flotation: self.plane.zero_point + longitudinal * centroid.x + transverse * centroid.y,
+            transverse_moment: central_moment.y,
+            longitudinal_moment: central_moment.x
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::geometry::Plane;
+
+    #[test]
+    fn test_box_waterplane(){
+        //4 long, 2 high and 2 wide, bow along +x
+        let hull = Hull::try_from(Mesh::from(shape::Box::new(4.0, 2.0, 2.0))).unwrap();
+        let clipped = hull.clip_with_plane(&Plane{
+            normal: Vec3::Y,
+            zero_point: Vec3::new(0.0, 0.25, 0.0)
+        });
+
+        let waterplane = clipped.waterplane(Vec3::X);
+        assert!((waterplane.area - 8.0).abs() < 1e-4);
+        assert!((waterplane.centre_of_flotation - Vec3::new(0.0, 0.25, 0.0)).length() < 1e-4);
+        //L * B^3 / 12 and B * L^3 / 12
+        assert!((waterplane.transverse_moment - 4.0 * 8.0 / 12.0).abs() < 1e-4);
+        assert!((waterplane.longitudinal_moment - 2.0 * 64.0 / 12.0).abs() < 1e-4);
+    }
+}
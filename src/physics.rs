@@ -1,22 +1,95 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use crate::hull::clipping::ClipTolerance;
+
 pub mod liquids;
 
-pub struct PhysicsPlugin;
+pub struct PhysicsPlugin{
+    ///Gravity written into Rapier's configuration at startup, `None` leaves the gravity the app gave Rapier alone.
+    pub gravity: Option<Vec3>,
+    ///Direction liquids push floating hulls and measure surface heights along, `None` points it against Rapier's gravity.
+    pub up: Option<Vec3>,
+    ///Turn off when the app already adds `RapierPhysicsPlugin` itself.
+    pub add_rapier_plugin: bool,
+    ///Turn off when the app already adds `RapierDebugRenderPlugin` itself, or does not want it.
+    pub add_debug_render_plugin: bool,
+    ///How close hull vertices have to be to a liquid's surface or bounds to lie on them.
+    pub clip_tolerance: ClipTolerance
+}
+
+impl Default for PhysicsPlugin{
+    fn default() -> Self {
+        Self {
+            gravity: None,
+            up: None,
+            add_rapier_plugin: true,
+            add_debug_render_plugin: true,
+            clip_tolerance: ClipTolerance::default()
+        }
+    }
+}
+
+///Settings shared by the physics systems, inserted by [`PhysicsPlugin`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsConfig{
+    pub gravity: Option<Vec3>,
+    ///Fixed up axis, `None` follows Rapier's gravity, see [`PhysicsConfig::up`].
+    pub up: Option<Vec3>,
+    pub clip_tolerance: ClipTolerance
+}
+
+impl Default for PhysicsConfig{
+    fn default() -> Self {
+        Self { gravity: None, up: None, clip_tolerance: ClipTolerance::default() }
+    }
+}
+
+impl PhysicsConfig{
+    ///The unit up axis under `gravity`, the configured one or straight against gravity.
+    ///Without either, such as in zero gravity, it is +Y.
+    pub fn up(&self, gravity: Vec3) -> Vec3{
+        self.up.unwrap_or(-gravity).try_normalize().unwrap_or(Vec3::Y)
+    }
+}
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(liquids::LiquidsPlugin)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugins(RapierDebugRenderPlugin::default())
+        app.insert_resource(PhysicsConfig{gravity: self.gravity, up: self.up, clip_tolerance: self.clip_tolerance})
+        .add_plugins(liquids::LiquidsPlugin)
         .add_systems(Startup, startup_system);
+
+        if self.add_rapier_plugin{
+            app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default());
+        }
+        if self.add_debug_render_plugin{
+            app.add_plugins(RapierDebugRenderPlugin::default());
+        }
+    }
+}
+
+
+fn startup_system(physics_config: Res<PhysicsConfig>, mut config : ResMut<RapierConfiguration>){
+    if let Some(gravity) = physics_config.gravity{
+        config.gravity = gravity;
     }
 }
 
+#[cfg(test)]
+mod tests{
+    use super::*;
 
-fn startup_system(mut config : ResMut<RapierConfiguration>){
-    config.gravity = Vec3{
-        x:0.0, y:0.0, z:-9.8
+    #[test]
+    fn test_host_gravity_is_kept(){
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+        .insert_resource(RapierContext::default())
+        .insert_resource(RapierConfiguration{gravity: Vec3::NEG_Z * 3.71, ..default()})
+        .add_plugins(PhysicsPlugin{add_rapier_plugin: false, add_debug_render_plugin: false, ..default()});
+        app.update();
+
+        let gravity = app.world.resource::<RapierConfiguration>().gravity;
+        assert_eq!(gravity, Vec3::NEG_Z * 3.71);
+        assert_eq!(app.world.resource::<PhysicsConfig>().up(gravity), Vec3::Z);
     }
-}
\ No newline at end of file
+}
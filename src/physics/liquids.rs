@@ -1,17 +1,348 @@
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::geometry::{Plane, surface::LiquidSurface, waves::{AiryWaves, GerstnerWaves, HeightfieldSurface}};
 
 pub mod buoyancy;
+pub mod drag;
+pub mod impact;
+pub mod ocean;
+pub mod submersion;
+pub mod surface_mesh;
 pub struct LiquidsPlugin;
 
 impl Plugin for LiquidsPlugin{
     fn build(&self, app: &mut App) {
-        app.add_plugins(buoyancy::BuoyancyPlugin);
+        app.init_resource::<super::PhysicsConfig>()
+        .configure_sets(Update, (LiquidSet::Submersion, LiquidSet::Forces, LiquidSet::Apply).chain())
+        .add_plugins(submersion::SubmersionPlugin)
+        .add_plugins(buoyancy::BuoyancyPlugin)
+        .add_plugins(drag::DragPlugin)
+        .add_plugins(impact::ImpactPlugin)
+        .add_plugins(ocean::OceanPlugin)
+        .add_plugins(surface_mesh::SurfaceMeshPlugin);
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiquidSet{
+    ///Clips hulls against the liquids they touch and clears the previous frame's liquid forces.
+    Submersion,
+    ///Force models adding onto each body's [`submersion::LiquidForce`].
+    Forces,
+    ///Moves the summed liquid forces onto each body's `ExternalForce`.
+    Apply
+}
+
+///Where the free surface of a liquid sits, relative to the liquid entity's transform.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Surface{
+    ///Flat surface at this height above the entity's origin, facing along the configured up axis.
+    Height(f32),
+    ///Any plane in the entity's local space, the normal points out of the liquid.
+    Plane(Plane),
+    ///Waves moving over a rest plane in the entity's local space, their time follows the app's clock.
+    ///Turning the entity turns the directions the waves travel in.
+    AiryWaves(AiryWaves),
+    GerstnerWaves(GerstnerWaves),
+    ///Sampled heights over a rest plane in the entity's local space.
+    Heightfield(HeightfieldSurface),
+    ///The world space sea of the [`ocean::OceanWaves`] resource, the entity's transform is ignored.
+    ///Without the resource the surface is flat through the entity's origin.
+    Ocean
+}
+
+fn transform_plane(plane: &Plane, transform: &GlobalTransform) -> Plane{
+    Plane{
+        zero_point: transform.transform_point(plane.zero_point),
+        //normals map with the inverse transpose so scaling keeps them perpendicular
+        normal: (transform.affine().matrix3.inverse().transpose() * plane.normal).normalize()
+    }
+}
+
+///A liquid's surface in world space, from [`Surface::to_world`]. The ocean is borrowed from its resource rather than copied.
+#[derive(Clone, Debug)]
+pub enum WorldSurface<'a>{
+    Plane(Plane),
+    AiryWaves(AiryWaves),
+    GerstnerWaves(GerstnerWaves),
+    Heightfield(HeightfieldSurface),
+    Ocean(&'a ocean::OceanWaves)
+}
+
+impl<'a> LiquidSurface for WorldSurface<'a>{
+    fn height_above(&self, point: Vec3) -> f32{
+        match self{
+            WorldSurface::Plane(plane) => plane.height_above(point),
+            WorldSurface::AiryWaves(waves) => waves.height_above(point),
+            WorldSurface::GerstnerWaves(waves) => waves.height_above(point),
+            WorldSurface::Heightfield(field) => field.height_above(point),
+            WorldSurface::Ocean(ocean) => ocean.height_above(point)
+        }
+    }
+
+    fn reference_plane(&self) -> Plane{
+        match self{
+            WorldSurface::Plane(plane) => *plane,
+            WorldSurface::AiryWaves(waves) => waves.rest,
+            WorldSurface::GerstnerWaves(waves) => waves.rest,
+            WorldSurface::Heightfield(field) => field.rest,
+            WorldSurface::Ocean(ocean) => ocean.reference_plane()
+        }
+    }
+
+    fn crossing(&self, a: Vec3, b: Vec3) -> Option<Vec3>{
+        match self{
+            WorldSurface::Plane(plane) => plane.crossing(a, b),
+            WorldSurface::AiryWaves(waves) => waves.crossing(a, b),
+            WorldSurface::GerstnerWaves(waves) => waves.crossing(a, b),
+            WorldSurface::Heightfield(field) => field.crossing(a, b),
+            WorldSurface::Ocean(ocean) => ocean.crossing(a, b)
+        }
     }
 }
 
-#[derive(Component, Default)]
+impl Surface{
+    ///The same surface moved into world space at `time` seconds, `up` is the configured up axis.
+    ///A `Surface` only becomes a [`LiquidSurface`] this way, heights are measured along `up`.
+    pub fn to_world<'a>(&self, transform: &GlobalTransform, up: Vec3, time: f32, ocean: Option<&'a ocean::OceanWaves>) -> WorldSurface<'a>{
+        match self{
+            Surface::Ocean => match ocean{
+                Some(ocean) => WorldSurface::Ocean(ocean),
+                None => Surface::Height(0.0).to_world(transform, up, time, None)
+            },
+            Surface::Height(height) => WorldSurface::Plane(Plane{
+                zero_point: transform.translation() + up * *height,
+                normal: up
+            }),
+            Surface::Plane(plane) => WorldSurface::Plane(transform_plane(plane, transform)),
+            Surface::AiryWaves(waves) => WorldSurface::AiryWaves(AiryWaves{
+                rest: transform_plane(&waves.rest, transform),
+                axis: transform.affine().matrix3 * waves.axis,
+                time,
+                ..waves.clone()
+            }),
+            Surface::GerstnerWaves(waves) => WorldSurface::GerstnerWaves(GerstnerWaves{
+                rest: transform_plane(&waves.rest, transform),
+                axis: transform.affine().matrix3 * waves.axis,
+                time,
+                ..waves.clone()
+            }),
+            Surface::Heightfield(field) => WorldSurface::Heightfield(HeightfieldSurface{
+                rest: transform_plane(&field.rest, transform),
+                axis: transform.affine().matrix3 * field.axis,
+                ..field.clone()
+            })
+        }
+    }
+}
+
+impl Default for Surface{
+    fn default() -> Self {
+        Self::Height(0.0)
+    }
+}
+
+///How far a liquid reaches below its surface, only the part of a hull inside the bounds is submerged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LiquidBounds{
+    ///Open water stretching out under the whole surface.
+    #[default]
+    Infinite,
+    ///A box centred on the entity's origin in its local space, such as a pool or tank.
+    ///The box only cuts off the sides and bottom, the waterline is still set by the liquid's [`Surface`].
+    ///The default surface is level with the entity's origin, so the box is half full, use
+    ///`Surface::Height(half_extents.y)` to fill it to the brim.
+    Box{half_extents: Vec3},
+    ///A convex volume in the entity's local space, the plane normals point out of the liquid.
+    Convex(Vec<Plane>),
+    ///The liquid's own Rapier collider. Cuboids and convex polyhedra are used as they are,
+    ///other shapes are approximated by their bounding box.
+    Collider
+}
+
+fn box_planes(centre: Vec3, half_extents: Vec3) -> Vec<Plane>{
+    let mut planes = Vec::with_capacity(6);
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z]{
+        for normal in [axis, -axis]{
+            planes.push(Plane{normal, zero_point: centre + normal * half_extents.dot(axis)});
+        }
+    }
+    return planes;
+}
+
+impl LiquidBounds{
+    ///The bounding planes in world space, empty for open water.
+    pub fn to_world(&self, transform: &GlobalTransform, collider: Option<&Collider>) -> Vec<Plane>{
+        match self{
+            LiquidBounds::Infinite => Vec::new(),
+            LiquidBounds::Box{half_extents} => box_planes(Vec3::ZERO, *half_extents).iter()
+                .map(|plane| transform_plane(plane, transform)).collect(),
+            LiquidBounds::Convex(planes) => planes.iter().map(|plane| transform_plane(plane, transform)).collect(),
+            LiquidBounds::Collider => {
+                let Some(collider) = collider else {return Vec::new()};
+                let local_planes = if let Some(cuboid) = collider.as_cuboid(){
+                    box_planes(Vec3::ZERO, cuboid.half_extents())
+                }else if let Some(polyhedron) = collider.as_convex_polyhedron(){
+                    let points: Vec<Vec3> = polyhedron.points().collect();
+                    polyhedron.raw.faces().iter().map(|face| {
+                        let normal = Vec3::new(face.normal.x, face.normal.y, face.normal.z);
+                        //the face lies on the point furthest along its normal
+                        let zero_point = points.iter().copied().max_by(|a, b| a.dot(normal).total_cmp(&b.dot(normal))).unwrap_or_default();
+                        Plane{normal, zero_point}
+                    }).collect()
+                }else{
+                    let aabb = collider.raw.compute_local_aabb();
+                    let mins = Vec3::new(aabb.mins.x, aabb.mins.y, aabb.mins.z);
+                    let maxs = Vec3::new(aabb.maxs.x, aabb.maxs.y, aabb.maxs.z);
+                    box_planes((mins + maxs) / 2.0, (maxs - mins) / 2.0)
+                };
+
+                //Rapier already scaled the shape, only move and turn it
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                local_planes.iter().map(|plane| Plane{
+                    normal: rotation * plane.normal,
+                    zero_point: translation + rotation * plane.zero_point
+                }).collect()
+            }
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug, PartialEq)]
 pub struct Liquid{
+    ///Density in kg/m^3.
+    pub density: f32,
+    ///Dynamic viscosity in Pa*s.
+    pub viscosity: f32,
+    pub surface: Surface,
+    pub bounds: LiquidBounds,
+    ///World space velocity of the liquid, for currents and rivers.
+    pub flow_velocity: Option<Vec3>
+}
+
+impl Liquid{
+    pub fn fresh_water() -> Self{
+        Self { density: 1000.0, viscosity: 1.0e-3, surface: Surface::default(), bounds: LiquidBounds::Infinite, flow_velocity: None }
+    }
+
+    pub fn sea_water() -> Self{
+        Self { density: 1025.0, viscosity: 1.08e-3, surface: Surface::default(), bounds: LiquidBounds::Infinite, flow_velocity: None }
+    }
+
+    ///Vegetable oil at room temperature.
+    pub fn oil() -> Self{
+        Self { density: 910.0, viscosity: 8.1e-2, surface: Surface::default(), bounds: LiquidBounds::Infinite, flow_velocity: None }
+    }
+
+    ///Velocity of the liquid itself, zero when it is still.
+    pub fn flow_velocity(&self) -> Vec3{
+        self.flow_velocity.unwrap_or(Vec3::ZERO)
+    }
+
+    ///The surface in world space at `time` seconds, `up` is the configured up axis.
+    pub fn world_surface<'a>(&self, transform: &GlobalTransform, up: Vec3, time: f32, ocean: Option<&'a ocean::OceanWaves>) -> WorldSurface<'a>{
+        self.surface.to_world(transform, up, time, ocean)
+    }
+
+    ///Planes bounding the liquid in world space, `collider` is only needed for [`LiquidBounds::Collider`].
+    pub fn world_bounds(&self, transform: &GlobalTransform, collider: Option<&Collider>) -> Vec<Plane>{
+        self.bounds.to_world(transform, collider)
+    }
+
+    ///Velocity of the liquid at a world space point, the flow plus the ocean's orbital motion.
+    pub fn velocity_at(&self, point: Vec3, ocean: Option<&ocean::OceanWaves>) -> Vec3{
+        let orbital_velocity = match (&self.surface, ocean){
+            (Surface::Ocean, Some(ocean)) => ocean.orbital_velocity(point),
+            _ => Vec3::ZERO
+        };
+        return self.flow_velocity() + orbital_velocity;
+    }
+}
+
+impl Default for Liquid{
+    fn default() -> Self {
+        Self::fresh_water()
+    }
 }
 
+#[cfg(test)]
+mod tests{
+    use std::time::Duration;
 
+    use bevy::ecs::schedule::IntoSystemConfigs;
 
+    use super::*;
+    use crate::hull::Hull;
+    use submersion::{Submersion, SubmergedPart, LiquidForce};
+
+    ///App running one liquid force system at a steady 60 Hz, without Rapier's physics step.
+    pub(super) fn force_test_app<M>(system: impl IntoSystemConfigs<M>) -> App{
+        let mut time = Time::default();
+        let start = time.startup();
+        time.update_with_instant(start);
+        time.update_with_instant(start + Duration::from_secs_f32(1.0 / 60.0));
+
+        let mut app = App::new();
+        app.insert_resource(time)
+        .insert_resource(RapierContext::default())
+        .insert_resource(RapierConfiguration::default())
+        .init_resource::<crate::physics::PhysicsConfig>()
+        .add_systems(Update, system);
+        return app;
+    }
+
+    ///Body at the origin with `hull` submerged below `surface` in fresh water, as the submersion system would leave it.
+    pub(super) fn submerged_body(app: &mut App, hull: &Hull, surface: Plane) -> Entity{
+        let liquid = app.world.spawn(Liquid::fresh_water()).id();
+        let clipped_hull = hull.clip_with_plane(&surface);
+        return app.world.spawn((
+            Submersion{parts: vec![SubmergedPart{liquid, clipped_hull}]},
+            LiquidForce::default(),
+            GlobalTransform::IDENTITY
+        )).id();
+    }
+
+    #[test]
+    fn test_height_follows_up(){
+        let transform = GlobalTransform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        let surface = Surface::Height(0.5).to_world(&transform, Vec3::Z, 0.0, None);
+        assert_eq!(surface.reference_plane(), Plane{zero_point: Vec3::new(1.0, 2.0, 3.5), normal: Vec3::Z});
+        assert!((surface.height_above(Vec3::new(0.0, 10.0, 4.0)) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_waves_turn_with_the_entity(){
+        let surface = Surface::AiryWaves(AiryWaves{
+            components: vec![crate::geometry::waves::WaveComponent{amplitude: 0.5, wavelength: 10.0, direction: Vec2::X, phase: 0.0}],
+            ..default()
+        });
+
+        //the crests run across the entity's x axis, half a wavelength along it is a trough
+        let straight = surface.to_world(&GlobalTransform::IDENTITY, Vec3::Y, 0.0, None);
+        assert!((straight.height_above(Vec3::X * 5.0) - 0.5).abs() < 1e-5);
+        assert!((straight.height_above(Vec3::Z * 5.0) + 0.5).abs() < 1e-5);
+
+        //yawed a quarter turn the entity's x axis points along -z, and the waves follow it
+        let yawed = surface.to_world(&GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2))), Vec3::Y, 0.0, None);
+        assert!((yawed.height_above(Vec3::NEG_Z * 5.0) - 0.5).abs() < 1e-5);
+        assert!((yawed.height_above(Vec3::X * 5.0) + 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_hull_straddling_box_wall(){
+        //a tank filled to the brim whose wall at x = 0 cuts a unit cube in half
+        let half_extents = Vec3::splat(1.0);
+        let liquid = Liquid{surface: Surface::Height(half_extents.y), bounds: LiquidBounds::Box{half_extents}, ..default()};
+        let liquid_transform = GlobalTransform::from_translation(Vec3::new(-1.0, 0.5, 0.0));
+
+        let hull = Hull::try_from(Mesh::from(shape::Cube::default())).unwrap();
+        let surface = liquid.world_surface(&liquid_transform, Vec3::Y, 0.0, None);
+        let bounds = liquid.world_bounds(&liquid_transform, None);
+        let (volume, centroid) = hull.clip_with_surface(&surface).clip_with_bounds(&bounds).volume_and_centroid();
+
+        //only the half inside the tank is submerged, however deep the cube sits
+        assert!((volume - 0.5).abs() < 1e-5, "{}", volume);
+        assert!(centroid.abs_diff_eq(Vec3::new(-0.25, 0.0, 0.0), 1e-5), "{}", centroid);
+    }
+}
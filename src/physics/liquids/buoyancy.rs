@@ -1,30 +1,94 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use super::{LiquidSet, submersion::{Submersion, LiquidForce}};
+
 pub struct BuoyancyPlugin;
 
 impl Plugin for BuoyancyPlugin{
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, buoyancy_system);
+        app.add_systems(Update, buoyancy_system.in_set(LiquidSet::Forces));
     }
 }
 
+///World space center of mass of a body, the point liquid torques are taken around.
+pub(super) fn center_of_mass(rapier_context: &RapierContext, entity: Entity, body_transform: &GlobalTransform) -> Vec3{
+    rapier_context.entity2body().get(&entity)
+        .and_then(|handle| rapier_context.bodies.get(*handle))
+        .map(|body| {
+            let point = body.center_of_mass();
+            Vec3::new(point.x, point.y, point.z)
+        })
+        .unwrap_or(body_transform.translation())
+}
 
 fn buoyancy_system(
     rapier_context: Res<RapierContext>,
-    config : ResMut<RapierConfiguration>,
-    mut ridgidbody_query: Query<(Entity,&RigidBody,&Collider),Without<super::Liquid>>,
-    liquid_query: Query<(&Collider, &super::Liquid)>
+    config : Res<RapierConfiguration>,
+    physics_config : Res<crate::physics::PhysicsConfig>,
+    mut ridgidbody_query: Query<(Entity, &Submersion, &GlobalTransform, &mut LiquidForce)>,
+    liquid_query: Query<&super::Liquid>
 ){
+    //the liquid's weight pushes back along the up axis, whichever way gravity is set up
+    let buoyant_acceleration = physics_config.up(config.gravity) * config.gravity.length();
+
     ridgidbody_query.par_iter_mut().for_each_mut(|
-            (entity, rigid_body, collider)
+            (entity, submersion, body_transform, mut liquid_force)
         |{
-            for (collider1, collider2, intersecting) in rapier_context.intersections_with(entity) {
-                if intersecting {
-                    for (liquid_collider, liquid) in liquid_query.get(collider2).iter(){
+            let center_of_mass = center_of_mass(&rapier_context, entity, body_transform);
+            let world_from_local = body_transform.affine();
+            let volume_scale = world_from_local.matrix3.determinant().abs();
+
+            for part in submersion.parts.iter(){
+                let Ok(liquid) = liquid_query.get(part.liquid) else {continue};
 
-                    }
+                let (local_volume, local_centroid) = part.clipped_hull.volume_and_centroid();
+                if local_volume <= 0.0{
+                    continue;
                 }
+
+                //Archimedes, the displaced liquid's weight pushes up through the centre of buoyancy
+                let displaced_volume = local_volume * volume_scale;
+                let centre_of_buoyancy = world_from_local.transform_point3(local_centroid);
+                let force = buoyant_acceleration * liquid.density * displaced_volume;
+
+                *liquid_force += ExternalForce::at_point(force, centre_of_buoyancy, center_of_mass);
             }
         });
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::geometry::Plane;
+    use crate::physics::liquids::tests::{force_test_app, submerged_body};
+
+    #[test]
+    fn test_half_submerged_cube(){
+        let mut app = force_test_app(buoyancy_system);
+        //a unit cube beside the body's origin, so the force's lever arm shows where it acts
+        let cube = crate::hull::Hull::try_from(Mesh::from(shape::Box{min_x: 0.0, max_x: 1.0, min_y: -0.5, max_y: 0.5, min_z: -0.5, max_z: 0.5})).unwrap();
+        let body = submerged_body(&mut app, &cube, Plane{normal: Vec3::Y, zero_point: Vec3::ZERO});
+        app.update();
+
+        let liquid_force = app.world.get::<LiquidForce>(body).unwrap();
+        let expected = Vec3::Y * 1000.0 * 9.81 * 0.5;
+        assert!((liquid_force.force - expected).length() < 1e-2, "{}", liquid_force.force);
+        //pushing up through the submerged half's centroid at (0.5, -0.25, 0)
+        let torque = Vec3::new(0.5, -0.25, 0.0).cross(expected);
+        assert!((liquid_force.torque - torque).length() < 1e-2, "{}", liquid_force.torque);
+    }
+
+    #[test]
+    fn test_follows_custom_gravity(){
+        //gravity pulling along -z, the liquid pushes the hull along +z
+        let mut app = force_test_app(buoyancy_system);
+        app.insert_resource(RapierConfiguration{gravity: Vec3::NEG_Z * 3.71, ..default()});
+        let cube = crate::hull::Hull::try_from(Mesh::from(shape::Cube::new(1.0))).unwrap();
+        let body = submerged_body(&mut app, &cube, Plane{normal: Vec3::Z, zero_point: Vec3::ZERO});
+        app.update();
+
+        let force = app.world.get::<LiquidForce>(body).unwrap().force;
+        assert!((force - Vec3::Z * 1000.0 * 3.71 * 0.5).length() < 1e-2, "{}", force);
+    }
+}
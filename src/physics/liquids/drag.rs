@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::{LiquidSet, submersion::{Submersion, LiquidForce}, buoyancy::center_of_mass};
+
+pub struct DragPlugin;
+
+impl Plugin for DragPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, drag_system.in_set(LiquidSet::Forces));
+    }
+}
+
+///Tunes the hydrodynamic forces on a hull, bodies without it use the defaults.
+///Only bodies with a Rapier `Velocity` component feel drag.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct HydrodynamicDrag{
+    ///Scales the pressure on faces pushing into the liquid, this is also what gives lift on planing hulls.
+    pub pressure_coefficient: f32,
+    ///Scales the suction on faces pulling away from the liquid.
+    pub suction_coefficient: f32,
+    ///Length used for the Reynolds number of the skin friction, usually the waterline length.
+    pub reference_length: f32
+}
+
+impl Default for HydrodynamicDrag{
+    fn default() -> Self {
+        Self { pressure_coefficient: 1.0, suction_coefficient: 0.5, reference_length: 1.0 }
+    }
+}
+
+///ITTC-57 friction line, the skin friction coefficient of a flat plate.
+fn friction_coefficient(reynolds_number: f32) -> f32{
+    //below this the line blows up, treat the flow as if it were at least this fast
+    let reynolds_number = reynolds_number.max(1e4);
+    let log = reynolds_number.log10() - 2.0;
+    return 0.075 / (log * log);
+}
+
+fn drag_system(
+    rapier_context: Res<RapierContext>,
+    ocean: Option<Res<super::ocean::OceanWaves>>,
+    mut ridgidbody_query: Query<(Entity, &Submersion, &GlobalTransform, &Velocity, Option<&HydrodynamicDrag>, &mut LiquidForce)>,
+    liquid_query: Query<&super::Liquid>
+){
+    let ocean = ocean.as_deref();
+
+    ridgidbody_query.par_iter_mut().for_each_mut(|
+            (entity, submersion, body_transform, velocity, drag, mut liquid_force)
+        |{
+            let drag = drag.copied().unwrap_or_default();
+            let center_of_mass = center_of_mass(&rapier_context, entity, body_transform);
+            let world_from_local = body_transform.affine();
+
+            for part in submersion.parts.iter(){
+                let Ok(liquid) = liquid_query.get(part.liquid) else {continue};
+
+                let kinematic_viscosity = liquid.viscosity / liquid.density;
+
+                let mut force = Vec3::ZERO;
+                let mut torque = Vec3::ZERO;
+                for triangle in part.clipped_hull.triangles(){
+                    let [a, b, c] = triangle.map(|vertex| world_from_local.transform_point3(vertex));
+                    let area_vector = (b - a).cross(c - a) / 2.0;
+                    let area = area_vector.length();
+                    if area <= f32::EPSILON{
+                        continue;
+                    }
+                    let normal = area_vector / area;
+                    let centre = (a + b + c) / 3.0;
+
+                    //velocity of the face relative to the liquid around it
+                    let face_velocity = velocity.linvel + velocity.angvel.cross(centre - center_of_mass);
+                    let relative_velocity = face_velocity - liquid.velocity_at(centre, ocean);
+                    let normal_speed = relative_velocity.dot(normal);
+                    let tangential_velocity = relative_velocity - normal * normal_speed;
+
+                    //pressure pushes on faces moving into the liquid and sucks on faces moving away,
+                    //either way it acts against the face's normal motion
+                    let pressure = if normal_speed > 0.0{
+                        0.5 * liquid.density * drag.pressure_coefficient * normal_speed * normal_speed
+                    }else{
+                        -0.5 * liquid.density * drag.suction_coefficient * normal_speed * normal_speed
+                    };
+                    let pressure_force = -pressure * area * normal;
+
+                    //skin friction drags along the face
+                    let speed = relative_velocity.length();
+                    let reynolds_number = speed * drag.reference_length / kinematic_viscosity;
+                    let friction_force = -0.5 * liquid.density * friction_coefficient(reynolds_number)
+                        * area * tangential_velocity.length() * tangential_velocity;
+
+                    let face_force = pressure_force + friction_force;
+                    force += face_force;
+                    torque += (centre - center_of_mass).cross(face_force);
+                }
+
+                *liquid_force += ExternalForce{force, torque};
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::geometry::Plane;
+    use crate::physics::liquids::tests::{force_test_app, submerged_body};
+
+    fn cube_under_water(app: &mut App, linvel: Vec3) -> Entity{
+        let cube = crate::hull::Hull::try_from(Mesh::from(shape::Cube::new(1.0))).unwrap();
+        let body = submerged_body(app, &cube, Plane{normal: Vec3::Y, zero_point: Vec3::Y * 10.0});
+        app.world.entity_mut(body).insert(Velocity{linvel, ..default()});
+        return body;
+    }
+
+    #[test]
+    fn test_pressure_drag(){
+        let mut app = force_test_app(drag_system);
+        let body = cube_under_water(&mut app, Vec3::X * 2.0);
+        app.update();
+
+        //0.5 * density * v^2 pushing on the front and half that sucking on the back, with a little skin friction on the sides
+        let force = app.world.get::<LiquidForce>(body).unwrap().force;
+        assert!(force.x < -3000.0 && force.x > -3100.0, "{}", force);
+        assert!(force.y.abs() < 1e-2 && force.z.abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_no_drag_at_rest(){
+        let mut app = force_test_app(drag_system);
+        let body = cube_under_water(&mut app, Vec3::ZERO);
+        app.update();
+        let liquid_force = app.world.get::<LiquidForce>(body).unwrap();
+        assert_eq!(liquid_force.force.length(), 0.0);
+        assert_eq!(liquid_force.torque.length(), 0.0);
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::{LiquidSet, submersion::{Submersion, LiquidForce}, buoyancy::center_of_mass};
+
+pub struct ImpactPlugin;
+
+impl Plugin for ImpactPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, impact_system.in_set(LiquidSet::Forces));
+    }
+}
+
+///Opt-in added-mass and slamming forces for fast hulls and objects dropped into liquid.
+///Both follow the momentum of the liquid dragged along with the hull, measured from how the
+///submerged volume and the waterline change between frames. Needs a Rapier `Velocity` component.
+///The frame a hull first touches a liquid counts as entering it from dry, so it still gets the slamming force.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct ImpactForces{
+    ///Added mass as a fraction of the displaced liquid's mass, 0.5 for a sphere.
+    pub added_mass_coefficient: f32,
+    ///Added mass of the wetted waterline as `coefficient * density * area^1.5`,
+    ///the default matches a flat disc hitting the surface.
+    pub slamming_coefficient: f32,
+    previous: Vec<ImpactState>
+}
+
+impl Default for ImpactForces{
+    fn default() -> Self {
+        Self {
+            added_mass_coefficient: 0.5,
+            slamming_coefficient: 4.0 / 3.0 / std::f32::consts::PI.powf(1.5),
+            previous: Vec::new()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ImpactState{
+    liquid: Entity,
+    //speed along the surface normal, negative when moving down into the liquid
+    normal_speed: f32,
+    //submerged volume in world space
+    volume: f32,
+    slamming_mass: f32
+}
+
+fn impact_system(
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+    ocean: Option<Res<super::ocean::OceanWaves>>,
+    mut ridgidbody_query: Query<(Entity, &Submersion, &GlobalTransform, &Velocity, &mut ImpactForces, &mut LiquidForce)>,
+    liquid_query: Query<&super::Liquid>
+){
+    let delta_seconds = time.delta_seconds();
+    if delta_seconds <= 0.0{
+        return;
+    }
+    let ocean = ocean.as_deref();
+
+    ridgidbody_query.par_iter_mut().for_each_mut(|
+            (entity, submersion, body_transform, velocity, mut impact, mut liquid_force)
+        |{
+            let center_of_mass = center_of_mass(&rapier_context, entity, body_transform);
+            let body_mass = rapier_context.entity2body().get(&entity)
+                .and_then(|handle| rapier_context.bodies.get(*handle))
+                .map_or(f32::INFINITY, |body| body.mass());
+            let world_from_local = body_transform.affine();
+            let volume_scale = world_from_local.matrix3.determinant().abs();
+            let area_scale = volume_scale.powf(2.0 / 3.0);
+
+            let mut current = Vec::with_capacity(submersion.parts.len());
+            for part in submersion.parts.iter(){
+                let Ok(liquid) = liquid_query.get(part.liquid) else {continue};
+
+                let (local_volume, local_centroid) = part.clipped_hull.volume_and_centroid();
+                if local_volume <= 0.0{
+                    continue;
+                }
+                let plane = part.clipped_hull.plane();
+                let normal = (world_from_local.matrix3.inverse().transpose() * plane.normal).normalize();
+                let waterplane = part.clipped_hull.waterplane(Vec3::X);
+
+                let centre_of_buoyancy = world_from_local.transform_point3(local_centroid);
+                let centre_of_flotation = world_from_local.transform_point3(waterplane.centre_of_flotation);
+                let relative_velocity = velocity.linvel + velocity.angvel.cross(centre_of_buoyancy - center_of_mass) - liquid.velocity_at(centre_of_buoyancy, ocean);
+                let normal_speed = relative_velocity.dot(normal);
+
+                let volume = local_volume * volume_scale;
+                //an explicit added mass heavier than the body itself would make the body oscillate out of control
+                let added_mass = (impact.added_mass_coefficient * liquid.density * volume).min(0.9 * body_mass);
+                let slamming_mass = impact.slamming_coefficient * liquid.density * (waterplane.area * area_scale).max(0.0).powf(1.5);
+
+                let state = ImpactState{liquid: part.liquid, normal_speed, volume, slamming_mass};
+                //a hull just touching the liquid came in at this speed with nothing wetted yet
+                let previous = impact.previous.iter().find(|previous| previous.liquid == part.liquid).cloned()
+                    .unwrap_or(ImpactState{volume: 0.0, slamming_mass: 0.0, ..state.clone()});
+
+                //d(m_a * v)/dt, the liquid pushed along resists the body's acceleration and the volume it newly sets moving
+                let acceleration = (normal_speed - previous.normal_speed) / delta_seconds;
+                let added_mass_rate = impact.added_mass_coefficient * liquid.density * (volume - previous.volume) / delta_seconds;
+                let added_mass_force = -(added_mass * acceleration + normal_speed * added_mass_rate) * normal;
+                *liquid_force += ExternalForce::at_point(added_mass_force, centre_of_buoyancy, center_of_mass);
+
+                //the waterline spreading out while entering sets more liquid in motion all at once
+                let slamming_mass_rate = (slamming_mass - previous.slamming_mass) / delta_seconds;
+                if normal_speed < 0.0 && slamming_mass_rate > 0.0{
+                    let slamming_force = -normal_speed * slamming_mass_rate * normal;
+                    *liquid_force += ExternalForce::at_point(slamming_force, centre_of_flotation, center_of_mass);
+                }
+                current.push(state);
+            }
+
+            impact.previous = current;
+        });
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::geometry::Plane;
+    use crate::physics::liquids::tests::{force_test_app, submerged_body};
+
+    //a 1 by 1 plate, 0.1 thick, with 1 cm of it under water
+    fn plate_in_water(app: &mut App, linvel: Vec3) -> Entity{
+        let plate = crate::hull::Hull::try_from(Mesh::from(shape::Box::new(1.0, 0.1, 1.0))).unwrap();
+        let surface = Plane{normal: Vec3::Y, zero_point: Vec3::Y * -0.04};
+        let body = submerged_body(app, &plate, surface);
+        app.world.entity_mut(body).insert((Velocity{linvel, ..default()}, ImpactForces::default()));
+        return body;
+    }
+
+    #[test]
+    fn test_slamming_on_entry(){
+        let mut app = force_test_app(impact_system);
+        let body = plate_in_water(&mut app, Vec3::NEG_Y * 5.0);
+        app.update();
+
+        //the first frame in the water is the hardest hit
+        let entry_force = app.world.get::<LiquidForce>(body).unwrap().force;
+        assert!(entry_force.y > 1e4, "{}", entry_force);
+
+        //held at the same depth and speed, the next frame sets no more liquid moving
+        app.world.get_mut::<LiquidForce>(body).unwrap().force = Vec3::ZERO;
+        app.update();
+        assert!(app.world.get::<LiquidForce>(body).unwrap().force.y.abs() < 1e-3 * entry_force.y);
+    }
+
+    #[test]
+    fn test_no_force_at_rest(){
+        let mut app = force_test_app(impact_system);
+        let body = plate_in_water(&mut app, Vec3::ZERO);
+        for _ in 0..2{
+            app.update();
+            assert_eq!(app.world.get::<LiquidForce>(body).unwrap().force, Vec3::ZERO);
+        }
+    }
+}
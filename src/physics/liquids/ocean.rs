@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+use crate::geometry::{Plane, surface::LiquidSurface, waves::{AiryWaves, WaveComponent, WAVE_GRAVITY}};
+
+use super::LiquidSet;
+
+pub struct OceanPlugin;
+
+impl Plugin for OceanPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, advance_ocean_system.before(LiquidSet::Submersion));
+    }
+}
+
+///Shape of the energy spectrum the ocean's waves are drawn from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaveSpectrum{
+    ///Fully developed sea, the wind has blown long enough over a long enough fetch.
+    PiersonMoskowitz,
+    ///Fetch limited sea with a sharper peak, 3.3 is the usual peak enhancement.
+    Jonswap{peak_enhancement: f32}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OceanSettings{
+    pub spectrum: WaveSpectrum,
+    ///Wind speed 10 m above the surface, in m/s.
+    pub wind_speed: f32,
+    ///Distance the wind has blown over open water in m, only used by JONSWAP.
+    pub fetch: f32,
+    ///World space direction the wind blows towards, projected onto the rest plane.
+    pub wind_direction: Vec3,
+    ///Mean sea level in world space.
+    pub rest: Plane,
+    pub frequency_count: usize,
+    pub direction_count: usize,
+    ///Seed for the random wave phases, the same seed gives the same sea.
+    pub seed: u64,
+    ///Gravitational acceleration in m/s^2 the spectrum and wave speeds are worked out for.
+    ///The [`OceanPlugin`] keeps it in step with the configured gravity.
+    pub gravity: f32
+}
+
+impl Default for OceanSettings{
+    fn default() -> Self {
+        Self {
+            spectrum: WaveSpectrum::Jonswap{peak_enhancement: 3.3},
+            wind_speed: 10.0,
+            fetch: 100_000.0,
+            wind_direction: Vec3::X,
+            rest: Plane::default(),
+            frequency_count: 16,
+            direction_count: 5,
+            seed: 0,
+            gravity: WAVE_GRAVITY
+        }
+    }
+}
+
+///Directional wave field drawn from a standard sea spectrum, advancing with the app's clock.
+///Liquids with [`super::Surface::Ocean`] float hulls on it.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct OceanWaves{
+    settings: OceanSettings,
+    waves: AiryWaves
+}
+
+///Small deterministic generator for the wave phases (splitmix64).
+fn next_random(state: &mut u64) -> f32{
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+    return (z >> 40) as f32 / (1u64 << 24) as f32;
+}
+
+impl OceanSettings{
+    ///Peak angular frequency and the spectrum's energy scale (Phillips constant).
+    fn peak(&self) -> (f32, f32){
+        let wind_speed = self.wind_speed.max(0.1);
+        let gravity = self.gravity;
+        match self.spectrum{
+            WaveSpectrum::PiersonMoskowitz => (0.855 * gravity / wind_speed, 8.1e-3),
+            WaveSpectrum::Jonswap{..} => {
+                let fetch = self.fetch.max(1.0);
+                let peak_frequency = 22.0 * (gravity * gravity / (wind_speed * fetch)).powf(1.0 / 3.0);
+                let phillips = 0.076 * (wind_speed * wind_speed / (fetch * gravity)).powf(0.22);
+                (peak_frequency, phillips)
+            }
+        }
+    }
+
+    ///Spectral energy density at an angular frequency, in m^2 s.
+    pub fn spectral_density(&self, angular_frequency: f32) -> f32{
+        let (peak_frequency, phillips) = self.peak();
+        let omega = angular_frequency;
+        let mut density = phillips * self.gravity * self.gravity / omega.powi(5) * (-1.25 * (peak_frequency / omega).powi(4)).exp();
+
+        if let WaveSpectrum::Jonswap{peak_enhancement} = self.spectrum{
+            let sigma = if omega <= peak_frequency {0.07} else {0.09};
+            let r = (-(omega - peak_frequency).powi(2) / (2.0 * sigma * sigma * peak_frequency * peak_frequency)).exp();
+            density *= peak_enhancement.powf(r);
+        }
+        return density;
+    }
+}
+
+impl OceanWaves{
+    pub fn new(settings: OceanSettings) -> Self{
+        let mut waves = AiryWaves{rest: settings.rest, gravity: settings.gravity, ..default()};
+        let (u, v) = waves.basis();
+        let wind = Vec2::new(settings.wind_direction.dot(u), settings.wind_direction.dot(v)).normalize_or_zero();
+        let wind = if wind == Vec2::ZERO {Vec2::X} else {wind};
+
+        let (peak_frequency, _) = settings.peak();
+        let frequency_count = settings.frequency_count.max(1);
+        let direction_count = settings.direction_count.max(1);
+
+        //most of the energy sits between a bit under the peak and a few times above it
+        let lowest = 0.6 * peak_frequency;
+        let highest = 4.0 * peak_frequency;
+        let frequency_step = (highest - lowest) / frequency_count as f32;
+        let direction_step = PI / direction_count as f32;
+
+        let mut random_state = settings.seed;
+        let mut components = Vec::with_capacity(frequency_count * direction_count);
+        for i in 0..frequency_count{
+            let omega = lowest + (i as f32 + 0.5) * frequency_step;
+            let energy = settings.spectral_density(omega) * frequency_step;
+            for j in 0..direction_count{
+                //cos^2 spreading around the wind over a half circle
+                let angle = -PI / 2.0 + (j as f32 + 0.5) * direction_step;
+                let spreading = 2.0 / PI * angle.cos().powi(2) * direction_step;
+                let amplitude = (2.0 * energy * spreading).sqrt();
+
+                components.push(WaveComponent{
+                    amplitude,
+                    wavelength: TAU * settings.gravity / (omega * omega),
+                    direction: Vec2::from_angle(angle).rotate(wind),
+                    phase: next_random(&mut random_state) * TAU
+                });
+            }
+        }
+
+        waves.components = components;
+        return Self { settings, waves };
+    }
+
+    pub fn settings(&self) -> &OceanSettings{
+        &self.settings
+    }
+
+    ///The wave components making up the sea at the current time.
+    pub fn waves(&self) -> &AiryWaves{
+        &self.waves
+    }
+
+    pub fn set_time(&mut self, time: f32){
+        self.waves.time = time;
+    }
+
+    ///Draws the sea again for a different gravity, keeping the settings' seed and the current time.
+    pub fn set_gravity(&mut self, gravity: f32){
+        let time = self.waves.time;
+        *self = Self::new(OceanSettings{gravity, ..self.settings.clone()});
+        self.set_time(time);
+    }
+
+    ///Height of the sea above the rest plane under (or over) `point`.
+    pub fn height(&self, point: Vec3) -> f32{
+        self.waves.elevation(self.waves.plane_coordinates(point).0)
+    }
+
+    ///World space surface normal under (or over) `point`.
+    pub fn normal(&self, point: Vec3) -> Vec3{
+        let (position, _) = self.waves.plane_coordinates(point);
+        let mut slope = Vec2::ZERO;
+        for wave in self.waves.components.iter(){
+            let direction = wave.direction.normalize();
+            let angle = wave.angle(position, self.waves.time, self.waves.gravity);
+            slope -= wave.amplitude * wave.wave_number() * angle.sin() * direction;
+        }
+        let (u, v) = self.waves.basis();
+        return (self.waves.rest.normal.normalize() - u * slope.x - v * slope.y).normalize();
+    }
+
+    ///World space velocity of the water at `point` from the waves' orbital motion, fading out with depth.
+    pub fn orbital_velocity(&self, point: Vec3) -> Vec3{
+        let (position, height) = self.waves.plane_coordinates(point);
+        let (u, v) = self.waves.basis();
+        let up = self.waves.rest.normal.normalize();
+
+        let mut velocity = Vec3::ZERO;
+        for wave in self.waves.components.iter(){
+            let direction = wave.direction.normalize();
+            let angle = wave.angle(position, self.waves.time, self.waves.gravity);
+            //above the rest plane the surface velocity carries on
+            let decay = (wave.wave_number() * height.min(0.0)).exp();
+            let speed = wave.amplitude * wave.angular_frequency(self.waves.gravity) * decay;
+
+            velocity += speed * angle.cos() * (u * direction.x + v * direction.y);
+            velocity += speed * angle.sin() * up;
+        }
+        return velocity;
+    }
+}
+
+impl LiquidSurface for OceanWaves{
+    fn height_above(&self, point: Vec3) -> f32{
+        self.waves.height_above(point)
+    }
+
+    fn reference_plane(&self) -> Plane{
+        self.waves.rest
+    }
+}
+
+fn advance_ocean_system(time: Res<Time>, rapier_config: Option<Res<bevy_rapier3d::prelude::RapierConfiguration>>, ocean: Option<ResMut<OceanWaves>>){
+    let Some(mut ocean) = ocean else {return};
+
+    //the waves on screen and under the hulls follow Rapier's gravity
+    let gravity = rapier_config.map_or(0.0, |config| config.gravity.length());
+    if gravity > 0.0 && gravity != ocean.settings().gravity{
+        ocean.set_gravity(gravity);
+    }
+    ocean.set_time(time.elapsed_seconds());
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_significant_wave_height(){
+        //fully developed sea at 10 m/s has a significant wave height a little over 2 m
+        let ocean = OceanWaves::new(OceanSettings{
+            spectrum: WaveSpectrum::PiersonMoskowitz,
+            frequency_count: 64,
+            ..default()
+        });
+        let variance: f32 = ocean.waves().components.iter().map(|wave| wave.amplitude * wave.amplitude / 2.0).sum();
+        let significant_height = 4.0 * variance.sqrt();
+        assert!(significant_height > 1.8 && significant_height < 2.4, "{}", significant_height);
+    }
+
+    #[test]
+    fn test_surface_velocity_matches_elevation(){
+        let mut ocean = OceanWaves::new(OceanSettings::default());
+        let point = Vec3::new(3.0, 0.0, -2.0);
+
+        //vertical orbital velocity at the surface is how fast the surface rises
+        let step = 1e-2;
+        ocean.set_time(1.0);
+        let before = ocean.height(point);
+        let velocity = ocean.orbital_velocity(point).y;
+        ocean.set_time(1.0 + step);
+        let after = ocean.height(point);
+        assert!(((after - before) / step - velocity).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_follows_configured_gravity(){
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+        .insert_resource(bevy_rapier3d::prelude::RapierConfiguration{gravity: Vec3::NEG_Y * 3.71, ..default()})
+        .insert_resource(OceanWaves::new(OceanSettings::default()))
+        .add_plugins(OceanPlugin);
+        app.update();
+
+        //the sea is drawn again for the weaker gravity, the same wind raises longer waves
+        let ocean = app.world.resource::<OceanWaves>();
+        let earth = OceanWaves::new(OceanSettings::default());
+        assert_eq!(ocean.waves().gravity, 3.71);
+        assert!(ocean.waves().components[0].wavelength > earth.waves().components[0].wavelength);
+    }
+}
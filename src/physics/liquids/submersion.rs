@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::geometry::{Plane, surface::{LiquidSurface, TransformedSurface}};
+use crate::hull::{Hull, ClippedHull};
+
+use super::LiquidSet;
+
+pub struct SubmersionPlugin;
+
+impl Plugin for SubmersionPlugin{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (insert_submersion_system, apply_deferred, submersion_system).chain().in_set(LiquidSet::Submersion))
+        .add_systems(Update, apply_liquid_force_system.in_set(LiquidSet::Apply));
+    }
+}
+
+///The part of a hull below one liquid's surface, in the body's local space.
+pub struct SubmergedPart{
+    pub liquid: Entity,
+    pub clipped_hull: ClippedHull
+}
+
+///Every liquid a hull is currently in, refreshed each frame before the liquid forces run.
+#[derive(Component, Default)]
+pub struct Submersion{
+    pub parts: Vec<SubmergedPart>
+}
+
+///Sum of the liquid forces on a body this frame, the force models add onto it.
+///It replaces its own share of the body's `ExternalForce` each frame, forces put there by anything else are kept.
+///Game code sharing the `ExternalForce` has to add to and take away from it, assigning it a new value
+///wipes out the liquid forces until the next frame and leaves them counted into the game's share after that.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct LiquidForce{
+    pub force: Vec3,
+    pub torque: Vec3,
+    //what was added onto the body's ExternalForce last frame
+    applied: ExternalForce,
+    //everything else on the ExternalForce, kept apart so it does not pick up rounding errors frame after frame
+    others: ExternalForce,
+    //the ExternalForce as it was left last frame, anything different has been changed by someone else
+    written: ExternalForce
+}
+
+impl std::ops::AddAssign<ExternalForce> for LiquidForce{
+    fn add_assign(&mut self, other: ExternalForce){
+        self.force += other.force;
+        self.torque += other.torque;
+    }
+}
+
+fn insert_submersion_system(
+    mut commands: Commands,
+    hull_query: Query<(Entity, Option<&ExternalForce>), (With<Hull>, With<RigidBody>, Without<Submersion>)>
+){
+    for (entity, external_force) in hull_query.iter(){
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((Submersion::default(), LiquidForce::default()));
+        //the liquid forces reach Rapier through it
+        if external_force.is_none(){
+            entity_commands.insert(ExternalForce::default());
+        }
+    }
+}
+
+fn submersion_system(
+    rapier_context: Res<RapierContext>,
+    physics_config : Res<crate::physics::PhysicsConfig>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+    ocean: Option<Res<super::ocean::OceanWaves>>,
+    mut ridgidbody_query: Query<(Entity, &Hull, &GlobalTransform, &mut Submersion, &mut LiquidForce),(With<RigidBody>, With<Collider>, Without<super::Liquid>)>,
+    liquid_query: Query<(&GlobalTransform, &super::Liquid, &Collider)>
+){
+    let up = physics_config.up(rapier_config.gravity);
+    let elapsed_seconds = time.elapsed_seconds();
+    let ocean = ocean.as_deref();
+
+    ridgidbody_query.par_iter_mut().for_each_mut(|
+            (entity, hull, body_transform, mut submersion, mut liquid_force)
+        |{
+            //liquid forces are summed up from scratch every frame
+            liquid_force.force = Vec3::ZERO;
+            liquid_force.torque = Vec3::ZERO;
+
+            submersion.parts.clear();
+
+            let world_from_local = body_transform.affine();
+
+            for (collider1, collider2, intersecting) in rapier_context.intersections_with(entity) {
+                if intersecting {
+                    let other = if collider1 == entity {collider2} else {collider1};
+                    for (liquid_transform, liquid, liquid_collider) in liquid_query.get(other).iter(){
+                        //the surface is seen from the hull's local space, so the hull is clipped where it is
+                        let surface = liquid.world_surface(liquid_transform, up, elapsed_seconds, ocean);
+                        let local_surface = TransformedSurface::new(&surface, world_from_local);
+                        let mut clipped_hull = hull.clip_with_tolerance(&local_surface, physics_config.clip_tolerance);
+
+                        let bounds = liquid.world_bounds(liquid_transform, Some(liquid_collider));
+                        if !bounds.is_empty(){
+                            let local_bounds: Vec<Plane> = bounds.iter()
+                                .map(|plane| TransformedSurface::new(plane, world_from_local).reference_plane())
+                                .collect();
+                            clipped_hull = clipped_hull.clip_with_bounds(&local_bounds);
+                        }
+
+                        submersion.parts.push(SubmergedPart{
+                            liquid: other,
+                            clipped_hull
+                        });
+                    }
+                }
+            }
+        });
+}
+
+//Swaps last frame's liquid forces on the ExternalForce for this frame's
+fn apply_liquid_force_system(
+    mut ridgidbody_query: Query<(&mut LiquidForce, &mut ExternalForce)>
+){
+    for (mut liquid_force, mut external_force) in ridgidbody_query.iter_mut(){
+        if *external_force != liquid_force.written{
+            liquid_force.others = *external_force - liquid_force.applied;
+        }
+        liquid_force.applied = ExternalForce{force: liquid_force.force, torque: liquid_force.torque};
+        *external_force = liquid_force.others + liquid_force.applied;
+        liquid_force.written = *external_force;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn test_other_forces_are_kept(){
+        let mut app = App::new();
+        app.add_systems(Update, apply_liquid_force_system);
+
+        let game_force = ExternalForce{force: Vec3::X, torque: Vec3::Z};
+        let entity = app.world.spawn((game_force, LiquidForce{force: Vec3::Y, ..default()})).id();
+        app.update();
+
+        //the next frame's liquid force replaces the last one without touching the game's share
+        app.world.get_mut::<LiquidForce>(entity).unwrap().force = Vec3::Y * 2.0;
+        app.update();
+        let external_force = app.world.get::<ExternalForce>(entity).unwrap();
+        assert_eq!(external_force.force, Vec3::new(1.0, 2.0, 0.0));
+        assert_eq!(external_force.torque, Vec3::Z);
+    }
+
+    #[test]
+    fn test_external_force_is_inserted(){
+        let mut app = App::new();
+        app.add_systems(Update, (insert_submersion_system, apply_deferred, apply_liquid_force_system).chain());
+
+        let hull = Hull::try_from(Mesh::from(shape::Cube::default())).unwrap();
+        let entity = app.world.spawn((hull, RigidBody::Dynamic)).id();
+        app.update();
+        app.world.get_mut::<LiquidForce>(entity).unwrap().force = Vec3::Y;
+        app.update();
+        assert_eq!(app.world.get::<ExternalForce>(entity).unwrap().force, Vec3::Y);
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::{prelude::*, render::{render_resource::PrimitiveTopology, mesh::Indices}};
+
+use crate::geometry::surface::LiquidSurface;
+use crate::physics::PhysicsConfig;
+
+use super::{Liquid, LiquidSet, ocean::OceanWaves};
+
+///Draws liquids that carry a [`LiquidMesh`], can be added on its own when only the visuals are wanted.
+pub struct SurfaceMeshPlugin;
+
+impl Plugin for SurfaceMeshPlugin{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsConfig>()
+        .add_systems(Update, surface_mesh_system.after(LiquidSet::Submersion));
+    }
+}
+
+///Gives a liquid a grid mesh that follows its surface, rebuilt every frame.
+///The grid is centred under the entity and the mesh handle is inserted on it, add a material to see it.
+#[derive(Component, Clone, Debug)]
+pub struct LiquidMesh{
+    ///Extent of the grid along the surface.
+    pub size: Vec2,
+    ///Number of quads along each side.
+    pub subdivisions: UVec2,
+    mesh: Option<Handle<Mesh>>,
+    //grid the mesh's indices and UVs were laid out for
+    mesh_subdivisions: UVec2
+}
+
+impl LiquidMesh{
+    pub fn new(size: Vec2, subdivisions: UVec2) -> Self{
+        Self { size, subdivisions, mesh: None, mesh_subdivisions: UVec2::ZERO }
+    }
+}
+
+impl Default for LiquidMesh{
+    fn default() -> Self {
+        Self::new(Vec2::splat(10.0), UVec2::splat(64))
+    }
+}
+
+fn grid_indices(subdivisions: UVec2) -> Vec<u32>{
+    let columns = subdivisions.x + 1;
+    let mut indices = Vec::with_capacity((subdivisions.x * subdivisions.y * 6) as usize);
+    for row in 0..subdivisions.y{
+        for column in 0..subdivisions.x{
+            let corner = row * columns + column;
+            //counter clockwise around the surface normal
+            indices.extend_from_slice(&[corner, corner + 1, corner + columns + 1]);
+            indices.extend_from_slice(&[corner, corner + columns + 1, corner + columns]);
+        }
+    }
+    return indices;
+}
+
+fn surface_mesh_system(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    physics_config: Res<PhysicsConfig>,
+    rapier_config: Option<Res<bevy_rapier3d::prelude::RapierConfiguration>>,
+    time: Res<Time>,
+    ocean: Option<Res<OceanWaves>>,
+    mut liquid_query: Query<(Entity, &Liquid, &GlobalTransform, &mut LiquidMesh)>
+){
+    let Some(mut meshes) = meshes else {return};
+    let ocean = ocean.as_deref();
+    //drawn without Rapier the liquid sits under ordinary gravity
+    let up = physics_config.up(rapier_config.map_or(Vec3::NEG_Y, |config| config.gravity));
+
+    for (entity, liquid, transform, mut liquid_mesh) in liquid_query.iter_mut(){
+        let subdivisions = liquid_mesh.subdivisions.max(UVec2::ONE);
+        let surface = liquid.world_surface(transform, up, time.elapsed_seconds(), ocean);
+        let plane = surface.reference_plane();
+        let up = plane.normal.normalize();
+        //the grid turns with the entity like its waves do
+        let (u, v) = plane.basis_along(transform.affine().matrix3 * Vec3::X);
+        let local_from_world = transform.affine().inverse();
+
+        //lay the grid out on the reference plane right under the entity, then drop every vertex onto the surface
+        let centre = transform.translation() - up * plane.distance_from_plane(transform.translation());
+        let columns = subdivisions.x + 1;
+        let rows = subdivisions.y + 1;
+        let mut world_positions = Vec::with_capacity((columns * rows) as usize);
+        let mut uvs = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows{
+            for column in 0..columns{
+                let fraction = Vec2::new(column as f32 / subdivisions.x as f32, row as f32 / subdivisions.y as f32);
+                let offset = (fraction - 0.5) * liquid_mesh.size;
+                let point = centre + u * offset.x + v * offset.y;
+                world_positions.push(point - up * surface.height_above(point));
+                uvs.push([fraction.x, fraction.y]);
+            }
+        }
+
+        let indices = grid_indices(subdivisions);
+
+        //area weighted normals from the displaced triangles
+        let mut world_normals = vec![Vec3::ZERO; world_positions.len()];
+        for triangle in indices.chunks_exact(3){
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| world_positions[index as usize]);
+            let face_normal = (b - a).cross(c - a);
+            for index in triangle{
+                world_normals[*index as usize] += face_normal;
+            }
+        }
+
+        let normal_from_world = transform.affine().matrix3.transpose();
+        let positions: Vec<[f32;3]> = world_positions.iter().map(|point| local_from_world.transform_point3(*point).to_array()).collect();
+        let normals: Vec<[f32;3]> = world_normals.iter().map(|normal| (normal_from_world * normal.normalize_or_zero()).normalize_or_zero().to_array()).collect();
+
+        let regrid = liquid_mesh.mesh_subdivisions != subdivisions;
+        liquid_mesh.mesh_subdivisions = subdivisions;
+        let existing_mesh = liquid_mesh.mesh.as_ref().and_then(|handle| meshes.get_mut(handle));
+        match existing_mesh{
+            Some(mesh) => {
+                mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+                mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+                //a 4x16 and a 16x4 grid have as many indices but lay them out differently
+                if regrid{
+                    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+                    mesh.set_indices(Some(Indices::U32(indices)));
+                }
+            },
+            None => {
+                let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+                mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+                mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+                mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+                mesh.set_indices(Some(Indices::U32(indices)));
+
+                let handle = meshes.add(mesh);
+                commands.entity(entity).insert(handle.clone());
+                liquid_mesh.mesh = Some(handle);
+            }
+        }
+    }
+}